@@ -44,18 +44,15 @@ async fn main() {
     let start = Local::now();
     let end = Local::now().checked_add_signed(Duration::days(7)).unwrap();
 
-    let mut event_list = Vec::new();
-    for calendar in list {
-        event_list.extend(
-            event_client
-                .list(calendar.id.clone(), start, end)
-                .await
-                .unwrap(),
-        );
+    let agenda = event_client
+        .agenda(list.into_iter().map(|calendar| calendar.id), start, end, 8)
+        .await;
+    for (calendar_id, error) in &agenda.failures {
+        eprintln!("[ERR] Failed to fetch {}: {}", calendar_id, error);
     }
 
     println!("Events: ");
-    for event in &event_list {
+    for event in &agenda.events {
         println!("  - {} : {}", event.summary, event.calendar_id);
     }
 }
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use futures::{Stream, TryStreamExt};
+use reqwest::Method;
+
+use super::{
+    channel::ChannelRequest, pagination, progenitor_support, Acl, AclRule, Channel, ClientResult,
+    GCalClient,
+};
+
+/// AclClient manages who a calendar is shared with. Requires a Google Calendar client.
+#[derive(Debug, Clone)]
+pub struct AclClient(Arc<GCalClient>);
+
+impl AclClient {
+    /// Construct an AclClient. Requires a Google Calendar Client.
+    pub fn new(client: Arc<GCalClient>) -> Self {
+        Self(client)
+    }
+
+    /// List every rule of the calendar, following all pages.
+    pub async fn list(&self, calendar_id: String) -> ClientResult<Vec<AclRule>> {
+        pagination::collect_items(self.list_stream(calendar_id), None).await
+    }
+
+    /// Lazily stream the rules of the calendar, fetching pages as needed.
+    pub fn list_stream(&self, calendar_id: String) -> impl Stream<Item = ClientResult<AclRule>> {
+        pagination::items(self.list_pages(calendar_id))
+    }
+
+    /// Lazily stream the pages of rules of the calendar.
+    pub fn list_pages(&self, calendar_id: String) -> impl Stream<Item = ClientResult<Acl>> {
+        let target = Acl::new(calendar_id.clone());
+        pagination::pages(self.0.clone(), move |token| {
+            let mut acl = target.clone();
+            if let Some(token) = token {
+                acl.add_query("pageToken".to_string(), token);
+            }
+            acl
+        })
+        .map_ok(move |mut acl: Acl| {
+            acl.items
+                .iter_mut()
+                .for_each(|r| r.calendar_id = calendar_id.clone());
+            acl
+        })
+    }
+
+    /// Get a rule by ID.
+    pub async fn get(&self, calendar_id: String, rule_id: String) -> ClientResult<AclRule> {
+        let rule = AclRule {
+            id: rule_id,
+            calendar_id: calendar_id.clone(),
+            ..Default::default()
+        };
+        let mut rule: AclRule = self.0.get(None, rule).await?.json().await?;
+        rule.calendar_id = calendar_id;
+        Ok(rule)
+    }
+
+    /// Share the calendar, optionally emailing the grantee about it.
+    pub async fn insert(
+        &self,
+        mut rule: AclRule,
+        send_notifications: bool,
+    ) -> ClientResult<AclRule> {
+        rule.id = String::new();
+        self.send_rule(Method::POST, rule, send_notifications).await
+    }
+
+    /// Replace a rule.
+    pub async fn update(&self, rule: AclRule, send_notifications: bool) -> ClientResult<AclRule> {
+        self.send_rule(Method::PUT, rule, send_notifications).await
+    }
+
+    /// Update a rule, only the role can actually change.
+    pub async fn patch(&self, rule: AclRule, send_notifications: bool) -> ClientResult<AclRule> {
+        self.send_rule(Method::PATCH, rule, send_notifications)
+            .await
+    }
+
+    /// Delete a rule, revoking the access it granted.
+    pub async fn delete(&self, calendar_id: String, rule_id: String) -> ClientResult<()> {
+        let rule = AclRule {
+            id: rule_id,
+            calendar_id,
+            ..Default::default()
+        };
+        self.0.delete(None, rule).await?;
+        Ok(())
+    }
+
+    /// Watch the rules of the calendar for changes. The returned channel carries the resource id
+    /// and expiration set by the server.
+    pub async fn watch(&self, calendar_id: String, channel: Channel) -> ClientResult<Channel> {
+        let target = ChannelRequest::new(
+            progenitor_support::encode_path(&format!("calendars/{}/acl/watch", calendar_id)),
+            Vec::new(),
+            channel,
+        );
+        Ok(self.0.post(None, target).await?.json().await?)
+    }
+
+    async fn send_rule(
+        &self,
+        method: Method,
+        mut rule: AclRule,
+        send_notifications: bool,
+    ) -> ClientResult<AclRule> {
+        let calendar_id = rule.calendar_id.clone();
+        rule.add_query(
+            "sendNotifications".to_string(),
+            send_notifications.to_string(),
+        );
+        let mut rule: AclRule = self
+            .0
+            .request(method, None, rule, Default::default())
+            .await?
+            .json()
+            .await?;
+        rule.calendar_id = calendar_id;
+        Ok(rule)
+    }
+}
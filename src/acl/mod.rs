@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+
+mod client;
+pub use client::*;
+
+use super::*;
+
+/* Google Calendar API: https://developers.google.com/calendar/api/v3/reference/acl#resource */
+
+/// AclRole is the access an ACL rule grants on a calendar.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AclRole {
+    /// No access at all.
+    #[default]
+    None,
+    FreeBusyReader,
+    Reader,
+    Writer,
+    Owner,
+}
+impl AclRole {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::FreeBusyReader => "freeBusyReader",
+            Self::Reader => "reader",
+            Self::Writer => "writer",
+            Self::Owner => "owner",
+        }
+    }
+}
+impl std::fmt::Display for AclRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+impl From<CalendarAccessRole> for AclRole {
+    fn from(role: CalendarAccessRole) -> Self {
+        match role {
+            CalendarAccessRole::Owner => Self::Owner,
+            CalendarAccessRole::Reader => Self::Reader,
+            CalendarAccessRole::Writer => Self::Writer,
+            CalendarAccessRole::FreeBusyReader => Self::FreeBusyReader,
+        }
+    }
+}
+
+/// AclScopeType is the kind of grantee of an ACL rule.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AclScopeType {
+    /// Anyone, the public scope.
+    #[default]
+    Default,
+    User,
+    Group,
+    Domain,
+}
+impl AclScopeType {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::User => "user",
+            Self::Group => "group",
+            Self::Domain => "domain",
+        }
+    }
+}
+impl std::fmt::Display for AclScopeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
+/// AclScope is who an ACL rule applies to.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AclScope {
+    #[serde(rename = "type")]
+    pub scope_type: AclScopeType,
+    /// Email of the user or group, or the domain name. Empty for the public scope.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+impl AclScope {
+    /// Anyone, including users without an account.
+    pub fn public() -> Self {
+        Self::default()
+    }
+
+    pub fn user(email: impl ToString) -> Self {
+        Self::with_value(AclScopeType::User, email)
+    }
+
+    pub fn group(email: impl ToString) -> Self {
+        Self::with_value(AclScopeType::Group, email)
+    }
+
+    pub fn domain(domain: impl ToString) -> Self {
+        Self::with_value(AclScopeType::Domain, domain)
+    }
+
+    fn with_value(scope_type: AclScopeType, value: impl ToString) -> Self {
+        Self {
+            scope_type,
+            value: Some(value.to_string()),
+        }
+    }
+}
+
+/// AclRule grants a role on a calendar to a scope.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AclRule {
+    #[serde(default = "default_rule_kind", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub etag: String,
+    /// Identifies the rule, set by the server, e.g. `user:alice@example.com`.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    pub scope: AclScope,
+    pub role: AclRole,
+
+    #[serde(skip)]
+    pub calendar_id: String,
+    #[serde(skip)]
+    query_string: QueryParams,
+}
+
+impl AclRule {
+    /// A new rule for the calendar, to be inserted with `AclClient::insert`.
+    pub fn new(calendar_id: impl ToString, scope: AclScope, role: AclRole) -> Self {
+        Self {
+            scope,
+            role,
+            calendar_id: calendar_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn add_query(&mut self, key: String, value: String) {
+        self.query_string.insert(key, value);
+    }
+}
+
+impl Sendable for AclRule {
+    fn path(&self, _action: Option<String>) -> String {
+        let path = if self.id.is_empty() {
+            format!("calendars/{}/acl", self.calendar_id)
+        } else {
+            format!("calendars/{}/acl/{}", self.calendar_id, self.id)
+        };
+        progenitor_support::encode_path(&path)
+    }
+
+    fn query(&self) -> QueryParams {
+        self.query_string.clone()
+    }
+}
+
+/// Acl is a page of the ACL rules of a calendar.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Acl {
+    #[serde(default = "default_acl_kind", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    pub etag: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_page_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_sync_token: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<AclRule>,
+
+    #[serde(skip)]
+    calendar_id: String,
+    #[serde(skip)]
+    query_string: QueryParams,
+}
+
+impl Acl {
+    pub(crate) fn new(calendar_id: String) -> Self {
+        Self {
+            calendar_id,
+            ..Default::default()
+        }
+    }
+
+    pub fn add_query(&mut self, key: String, value: String) {
+        self.query_string.insert(key, value);
+    }
+}
+
+impl Sendable for Acl {
+    fn path(&self, _action: Option<String>) -> String {
+        progenitor_support::encode_path(&format!("calendars/{}/acl", self.calendar_id))
+    }
+
+    fn query(&self) -> QueryParams {
+        self.query_string.clone()
+    }
+}
+
+impl Page for Acl {
+    type Item = AclRule;
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<AclRule> {
+        self.items
+    }
+}
+
+fn default_rule_kind() -> Option<String> {
+    Some("calendar#aclRule".to_string())
+}
+fn default_acl_kind() -> Option<String> {
+    Some("calendar#acl".to_string())
+}
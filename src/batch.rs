@@ -0,0 +1,425 @@
+use std::sync::Arc;
+
+use reqwest::{
+    header::{HeaderMap, HeaderValue, CONTENT_TYPE},
+    Method,
+};
+use serde::de::DeserializeOwned;
+
+use super::{ClientError, ClientResult, GCalClient, Sendable};
+
+/* Google API Source: https://developers.google.com/calendar/api/guides/batch */
+
+/// Google refuses batches with more operations than this, larger batches are sent in chunks.
+pub const MAX_BATCH_SIZE: usize = 50;
+
+const BOUNDARY: &str = "batch_gcal_rs";
+const CONTENT_TYPE_MULTIPART: &str = "multipart/mixed; boundary=batch_gcal_rs";
+
+/// Batch collects operations and sends them through the batch endpoint as `multipart/mixed`
+/// requests, which is much cheaper than sending them one by one. Obtain one with
+/// `GCalClient::batch`.
+#[derive(Debug, Clone)]
+pub struct Batch {
+    client: Arc<GCalClient>,
+    operations: Vec<BatchOperation>,
+}
+
+#[derive(Debug, Clone)]
+struct BatchOperation {
+    method: Method,
+    url: url::Url,
+    body: Option<Vec<u8>>,
+}
+
+/// BatchResponse is the response to a single successful operation of a batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl BatchResponse {
+    /// Deserialize the body of the response.
+    pub fn json<T: DeserializeOwned>(&self) -> ClientResult<T> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+}
+
+impl GCalClient {
+    /// Start a new batch of operations.
+    pub fn batch(self: Arc<Self>) -> Batch {
+        Batch {
+            client: self,
+            operations: Vec::new(),
+        }
+    }
+}
+
+impl Batch {
+    /// Add an operation to the batch.
+    pub fn add(
+        &mut self,
+        method: Method,
+        action: Option<String>,
+        target: impl Sendable,
+    ) -> ClientResult<&mut Self> {
+        let body = match method {
+            Method::POST | Method::PUT | Method::PATCH => Some(target.body_bytes()?),
+            _ => None,
+        };
+        self.operations.push(BatchOperation {
+            url: target.url(self.client.endpoints(), action)?,
+            method,
+            body,
+        });
+        Ok(self)
+    }
+
+    /// Add a GET operation to the batch.
+    pub fn get(
+        &mut self,
+        action: Option<String>,
+        target: impl Sendable,
+    ) -> ClientResult<&mut Self> {
+        self.add(Method::GET, action, target)
+    }
+
+    /// Add a POST operation to the batch.
+    pub fn post(
+        &mut self,
+        action: Option<String>,
+        target: impl Sendable,
+    ) -> ClientResult<&mut Self> {
+        self.add(Method::POST, action, target)
+    }
+
+    /// Add a PUT operation to the batch.
+    pub fn put(
+        &mut self,
+        action: Option<String>,
+        target: impl Sendable,
+    ) -> ClientResult<&mut Self> {
+        self.add(Method::PUT, action, target)
+    }
+
+    /// Add a PATCH operation to the batch.
+    pub fn patch(
+        &mut self,
+        action: Option<String>,
+        target: impl Sendable,
+    ) -> ClientResult<&mut Self> {
+        self.add(Method::PATCH, action, target)
+    }
+
+    /// Add a DELETE operation to the batch.
+    pub fn delete(
+        &mut self,
+        action: Option<String>,
+        target: impl Sendable,
+    ) -> ClientResult<&mut Self> {
+        self.add(Method::DELETE, action, target)
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Send every operation, `MAX_BATCH_SIZE` at a time. The results are in the order the
+    /// operations were added, failed operations are decoded the same way as single requests. When
+    /// a whole chunk fails, e.g. on a network error, every operation of that chunk carries the
+    /// error while the results of the other chunks are kept.
+    pub async fn send(self) -> Vec<ClientResult<BatchResponse>> {
+        let mut results = Vec::with_capacity(self.operations.len());
+        for chunk in self.operations.chunks(MAX_BATCH_SIZE) {
+            match self.send_chunk(chunk).await {
+                Ok(chunk_results) => results.extend(chunk_results),
+                Err(error) => {
+                    let copies: Vec<_> = (1..chunk.len()).map(|_| Err(copy(&error))).collect();
+                    results.push(Err(error));
+                    results.extend(copies);
+                }
+            }
+        }
+        results
+    }
+
+    async fn send_chunk(
+        &self,
+        chunk: &[BatchOperation],
+    ) -> ClientResult<Vec<ClientResult<BatchResponse>>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static(CONTENT_TYPE_MULTIPART),
+        );
+        // Google counts every operation of the batch against the quota, so does the rate limiter.
+        let resp = self
+            .client
+            .send_counted(
+                Method::POST,
+                url::Url::parse(&self.client.endpoints().batch)?,
+                Some(encode(chunk)),
+                headers,
+                chunk.len() as u32,
+            )
+            .await?;
+
+        let boundary = resp
+            .headers()
+            .get(CONTENT_TYPE)
+            .map(|h| h.to_str())
+            .transpose()?
+            .and_then(boundary)
+            .ok_or_else(|| {
+                ClientError::UnknownError("Batch response is missing its boundary".to_string())
+            })?;
+        let body = resp.text().await?;
+
+        Ok(results(chunk.len(), decode(&body, &boundary)))
+    }
+}
+
+/// A copy of the error that failed a whole chunk, for its other operations. Transport errors
+/// cannot be copied and are described instead.
+fn copy(error: &ClientError) -> ClientError {
+    match error {
+        ClientError::InvalidToken => ClientError::InvalidToken,
+        ClientError::HttpError(e) => ClientError::UnknownError(format!("Batch failed: {}", e)),
+        ClientError::BadRequest(e) => ClientError::BadRequest(e.clone()),
+        ClientError::Forbidden(e) => ClientError::Forbidden(e.clone()),
+        ClientError::NotFound(e) => ClientError::NotFound(e.clone()),
+        ClientError::Gone(e) => ClientError::Gone(e.clone()),
+        ClientError::PreconditionFailed(e) => ClientError::PreconditionFailed(e.clone()),
+        ClientError::RateLimited(e) => ClientError::RateLimited(e.clone()),
+        ClientError::QuotaExceeded(e) => ClientError::QuotaExceeded(e.clone()),
+        ClientError::ServerError(e) => ClientError::ServerError(e.clone()),
+        ClientError::ApiError(e) => ClientError::ApiError(e.clone()),
+        ClientError::TokenEndpointUnreachable(e) => {
+            ClientError::TokenEndpointUnreachable(e.clone())
+        }
+        ClientError::UnknownError(e) => ClientError::UnknownError(e.clone()),
+    }
+}
+
+/// The boundary of a `multipart/mixed` content type, quoted or not.
+fn boundary(content_type: &str) -> Option<String> {
+    content_type
+        .split(';')
+        .filter_map(|param| param.trim().split_once('='))
+        .find(|(key, _)| key.eq_ignore_ascii_case("boundary"))
+        .map(|(_, b)| b.trim_matches('"').to_string())
+}
+
+/// Match the decoded responses with the `len` operations of a chunk, by `Content-ID` or else by
+/// position. Operations without a response fail.
+fn results(
+    len: usize,
+    responses: Vec<(Option<usize>, BatchResponse)>,
+) -> Vec<ClientResult<BatchResponse>> {
+    let mut results: Vec<Option<ClientResult<BatchResponse>>> = (0..len).map(|_| None).collect();
+    for (position, (content_id, response)) in responses.into_iter().enumerate() {
+        let index = content_id.unwrap_or(position);
+        if let Some(slot) = results.get_mut(index) {
+            *slot = Some(if (200..300).contains(&response.status) {
+                Ok(response)
+            } else {
+                Err(ClientError::from_response(response.status, &response.body))
+            });
+        }
+    }
+    results
+        .into_iter()
+        .map(|r| {
+            r.unwrap_or_else(|| {
+                Err(ClientError::UnknownError(
+                    "Batch response is missing an operation".to_string(),
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Encode the operations as a `multipart/mixed` body, each part being an `application/http`
+/// request identified by its index.
+fn encode(operations: &[BatchOperation]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (i, op) in operations.iter().enumerate() {
+        body.extend_from_slice(
+            format!(
+                "--{BOUNDARY}\r\nContent-Type: application/http\r\nContent-ID: <item{i}>\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(
+            format!(
+                "{} {} HTTP/1.1\r\n",
+                op.method,
+                &op.url[url::Position::BeforePath..]
+            )
+            .as_bytes(),
+        );
+        match &op.body {
+            Some(content) => {
+                body.extend_from_slice(
+                    format!(
+                        "Content-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+                        content.len()
+                    )
+                    .as_bytes(),
+                );
+                body.extend_from_slice(content);
+                body.extend_from_slice(b"\r\n");
+            }
+            None => body.extend_from_slice(b"\r\n"),
+        }
+    }
+    body.extend_from_slice(format!("--{BOUNDARY}--\r\n").as_bytes());
+    body
+}
+
+/// Decode a `multipart/mixed` response into the responses it holds, along with the index of the
+/// operation they answer when the server echoed the `Content-ID`.
+fn decode(body: &str, boundary: &str) -> Vec<(Option<usize>, BatchResponse)> {
+    body.split(&format!("--{boundary}"))
+        .skip(1)
+        .take_while(|part| !part.starts_with("--"))
+        .filter_map(|part| {
+            let (part_headers, http) = split_head(part.trim_start_matches(['\r', '\n']))?;
+            let content_id = header_lines(part_headers)
+                .find(|(k, _)| k.eq_ignore_ascii_case("Content-ID"))
+                .and_then(|(_, v)| {
+                    v.trim_matches(['<', '>'])
+                        .strip_prefix("response-item")?
+                        .parse()
+                        .ok()
+                });
+
+            let (head, content) = split_head(http)?;
+            let (status_line, headers) = head.split_once('\n').unwrap_or((head, ""));
+            let status = status_line.split_whitespace().nth(1)?.parse().ok()?;
+            Some((
+                content_id,
+                BatchResponse {
+                    status,
+                    headers: header_lines(headers)
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect(),
+                    body: content.trim_end_matches(['\r', '\n']).as_bytes().to_vec(),
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Split a header block from what follows the blank line after it.
+fn split_head(text: &str) -> Option<(&str, &str)> {
+    text.split_once("\r\n\r\n")
+        .or_else(|| text.split_once("\n\n"))
+}
+
+fn header_lines(head: &str) -> impl Iterator<Item = (&str, &str)> {
+    head.lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(k, v)| (k.trim(), v.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operation(method: Method, path: &str, body: Option<&str>) -> BatchOperation {
+        BatchOperation {
+            method,
+            url: url::Url::parse(&format!("https://www.googleapis.com/calendar/v3/{path}"))
+                .unwrap(),
+            body: body.map(|b| b.as_bytes().to_vec()),
+        }
+    }
+
+    #[test]
+    fn encode_numbers_parts_in_order() {
+        let body = encode(&[
+            operation(Method::GET, "calendars/a/events/1", None),
+            operation(
+                Method::POST,
+                "calendars/a/events",
+                Some(r#"{"summary":"x"}"#),
+            ),
+        ]);
+        let body = String::from_utf8(body).unwrap();
+
+        let first = body.find("Content-ID: <item0>").unwrap();
+        let second = body.find("Content-ID: <item1>").unwrap();
+        assert!(first < second);
+        assert!(body.contains("GET /calendar/v3/calendars/a/events/1 HTTP/1.1\r\n"));
+        assert!(body.contains("Content-Length: 15\r\n\r\n{\"summary\":\"x\"}"));
+        assert!(body.ends_with("--batch_gcal_rs--\r\n"));
+    }
+
+    #[test]
+    fn decode_matches_responses_by_content_id() {
+        let body = "--b\r\n\
+            Content-Type: application/http\r\n\
+            Content-ID: <response-item1>\r\n\r\n\
+            HTTP/1.1 204 No Content\r\n\r\n\r\n\
+            --b\r\n\
+            Content-Type: application/http\r\n\
+            Content-ID: <response-item0>\r\n\r\n\
+            HTTP/1.1 200 OK\r\n\
+            Content-Type: application/json\r\n\r\n\
+            {\"id\":\"1\"}\r\n\
+            --b--\r\n";
+        let results = results(2, decode(body, "b"));
+
+        let first = results[0].as_ref().unwrap();
+        assert_eq!(first.status, 200);
+        assert_eq!(first.body, br#"{"id":"1"}"#);
+        assert_eq!(results[1].as_ref().unwrap().status, 204);
+    }
+
+    #[test]
+    fn decode_accepts_bare_newlines() {
+        let body = "--b\n\
+            Content-Type: application/http\n\
+            Content-ID: <response-item0>\n\n\
+            HTTP/1.1 404 Not Found\n\
+            Content-Type: application/json\n\n\
+            {\"error\":{\"code\":404,\"message\":\"Not Found\"}}\n\
+            --b--\n";
+        let results = results(1, decode(body, "b"));
+
+        assert!(matches!(results[0], Err(ClientError::NotFound(_))));
+    }
+
+    #[test]
+    fn boundary_may_be_quoted() {
+        assert_eq!(
+            boundary("multipart/mixed; boundary=batch_abc").as_deref(),
+            Some("batch_abc")
+        );
+        assert_eq!(
+            boundary("multipart/mixed; charset=UTF-8; boundary=\"batch_abc\"").as_deref(),
+            Some("batch_abc")
+        );
+        assert_eq!(boundary("application/json"), None);
+    }
+
+    #[test]
+    fn missing_parts_fail_their_operation() {
+        let body = "--b\r\n\
+            Content-Type: application/http\r\n\
+            Content-ID: <response-item1>\r\n\r\n\
+            HTTP/1.1 200 OK\r\n\r\n{}\r\n\
+            --b--\r\n";
+        let results = results(2, decode(body, "b"));
+
+        assert!(matches!(results[0], Err(ClientError::UnknownError(_))));
+        assert_eq!(results[1].as_ref().unwrap().status, 200);
+    }
+}
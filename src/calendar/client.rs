@@ -1,35 +1,361 @@
 use std::sync::Arc;
 
-use super::{CalendarAccessRole, CalendarList, CalendarListItem, ClientResult, GCalClient};
+use futures::Stream;
+use reqwest::{header::IF_NONE_MATCH, Method};
+use serde::Serialize;
+
+use super::{
+    channel::ChannelRequest,
+    etag_header,
+    fields::{items_mask, list_mask, Items},
+    pagination, sync, Calendar, CalendarAccessRole, CalendarList, CalendarListItem,
+    CalendarListQuery, Channel, ClientResult, Conditional, DefaultReminder, GCalClient,
+    NotificationSettings, Projection, QueryParams, Sendable, SyncResult,
+};
+
+/// CalendarClient manages the calendars themselves, as opposed to their entries in the user's
+/// calendar list. Requires a Google Calendar client.
+#[derive(Debug, Clone)]
+pub struct CalendarClient(Arc<GCalClient>);
+
+impl CalendarClient {
+    /// Construct a CalendarClient. Requires a Google Calendar Client.
+    pub fn new(client: Arc<GCalClient>) -> Self {
+        Self(client)
+    }
+
+    /// Get a calendar by ID, `primary` being the user's primary calendar.
+    pub async fn get(&self, calendar_id: String) -> ClientResult<Calendar> {
+        let calendar = Calendar {
+            id: calendar_id,
+            ..Default::default()
+        };
+        Ok(self.0.get(None, calendar).await?.json().await?)
+    }
+
+    /// Get a calendar by ID, unless it is unchanged since the copy with the etag was fetched.
+    pub async fn get_if_none_match(
+        &self,
+        calendar_id: String,
+        etag: &str,
+    ) -> ClientResult<Conditional<Calendar>> {
+        let calendar = Calendar {
+            id: calendar_id,
+            ..Default::default()
+        };
+        let resp = self
+            .0
+            .request(
+                Method::GET,
+                None,
+                calendar,
+                etag_header(IF_NONE_MATCH, etag)?,
+            )
+            .await?;
+        Conditional::from_response(resp).await
+    }
+
+    /// Create a secondary calendar, its ID is set by the server.
+    pub async fn insert(&self, mut calendar: Calendar) -> ClientResult<Calendar> {
+        calendar.id = String::new();
+        Ok(self.0.post(None, calendar).await?.json().await?)
+    }
+
+    /// Replace the metadata of a calendar.
+    pub async fn update(&self, calendar: Calendar) -> ClientResult<Calendar> {
+        Ok(self.0.put(None, calendar).await?.json().await?)
+    }
+
+    /// Update the metadata of a calendar, fields left unset or empty are kept.
+    pub async fn patch(&self, calendar: Calendar) -> ClientResult<Calendar> {
+        Ok(self.0.patch(None, calendar).await?.json().await?)
+    }
+
+    /// Delete a secondary calendar. Use `clear` for the primary calendar.
+    pub async fn delete(&self, calendar_id: String) -> ClientResult<()> {
+        let calendar = Calendar {
+            id: calendar_id,
+            ..Default::default()
+        };
+        self.0.delete(None, calendar).await?;
+        Ok(())
+    }
+
+    /// Delete every event of a primary calendar.
+    pub async fn clear(&self, calendar_id: String) -> ClientResult<()> {
+        let calendar = Calendar {
+            id: calendar_id,
+            ..Default::default()
+        };
+        self.0.post(Some("clear".to_string()), calendar).await?;
+        Ok(())
+    }
+}
 
 /// CalendarListClient is the method of accessing the calendar list. You must provide it with a
 /// Google Calendar client.
 #[derive(Debug, Clone)]
-pub struct CalendarListClient(Arc<GCalClient>);
+pub struct CalendarListClient(Arc<GCalClient>, Option<String>);
 
 impl CalendarListClient {
     /// Construct a CalendarListClient. Requires a Google Calendar Client.
     pub fn new(client: Arc<GCalClient>) -> Self {
-        Self(client)
+        Self(client, None)
+    }
+
+    /// Only request the fields in the mask from now on, e.g. `id,summary`. The mask selects the
+    /// fields of each entry: lists wrap it as `items(...)` and keep the page and sync tokens.
+    /// Fields that are left out take their default values.
+    pub fn with_fields(&self, fields: impl ToString) -> Self {
+        Self(self.0.clone(), Some(fields.to_string()))
     }
 
-    /// List the calendars. Currently only returns the first page of results.
+    /// The same client without its field mask, for callers that need whole entries.
+    pub(crate) fn unmasked(&self) -> Self {
+        Self(self.0.clone(), None)
+    }
+
+    /// Get the calendar list entry of a calendar.
+    pub async fn get(&self, calendar_id: String) -> ClientResult<CalendarListItem> {
+        let mut item = CalendarListItem::default();
+        item.id = calendar_id;
+        Ok(self.0.get(None, self.masked(item)).await?.json().await?)
+    }
+
+    /// Get the calendar list entry of a calendar, unless it is unchanged since the copy with the
+    /// etag was fetched.
+    pub async fn get_if_none_match(
+        &self,
+        calendar_id: String,
+        etag: &str,
+    ) -> ClientResult<Conditional<CalendarListItem>> {
+        let mut item = CalendarListItem::default();
+        item.id = calendar_id;
+        let resp = self
+            .0
+            .request(
+                Method::GET,
+                None,
+                self.masked(item),
+                etag_header(IF_NONE_MATCH, etag)?,
+            )
+            .await?;
+        Conditional::from_response(resp).await
+    }
+
+    /// Add an existing calendar, e.g. one shared with the user, to the calendar list. Only the ID
+    /// is required, the other fields set the user's preferences for it.
+    pub async fn insert(&self, item: CalendarListItem) -> ClientResult<CalendarListItem> {
+        let body = EntryBody::insert(self.masked(item));
+        Ok(self.0.post(None, body).await?.json().await?)
+    }
+
+    /// Replace the user's preferences for a calendar of the list, preferences left unset are
+    /// reset. Setting `background_color` or `foreground_color` sends them as RGB colors,
+    /// `color_id` is then picked by the server.
+    pub async fn update(&self, item: CalendarListItem) -> ClientResult<CalendarListItem> {
+        let body = EntryBody::write(self.masked(item));
+        Ok(self.0.put(None, body).await?.json().await?)
+    }
+
+    /// Update the user's preferences for a calendar of the list, optional fields left unset are
+    /// kept. Colors are handled as in `update`.
+    pub async fn patch(&self, item: CalendarListItem) -> ClientResult<CalendarListItem> {
+        let body = EntryBody::write(self.masked(item));
+        Ok(self.0.patch(None, body).await?.json().await?)
+    }
+
+    /// Remove a calendar from the list, unsubscribing from it.
+    pub async fn delete(&self, calendar_id: String) -> ClientResult<()> {
+        let mut item = CalendarListItem::default();
+        item.id = calendar_id;
+        self.0.delete(None, item).await?;
+        Ok(())
+    }
+
+    /// List every calendar, following all pages.
     pub async fn list(
         &self,
         hidden: bool,
         access_role: CalendarAccessRole,
     ) -> ClientResult<Vec<CalendarListItem>> {
-        // FIXME get all the results lol
-        let mut cl = CalendarList::default();
-        cl.add_query("minAccessRole".to_string(), access_role.to_string());
-        cl.add_query("showHidden".to_string(), hidden.to_string());
+        let query = CalendarListQuery::new()
+            .min_access_role(access_role)
+            .show_hidden(hidden);
+        pagination::collect_items(self.list_stream(query), None).await
+    }
 
-        Ok(self
-            .0
-            .get(None, cl)
-            .await?
-            .json::<CalendarList>()
-            .await?
-            .items)
+    /// Lazily stream the calendars matching the query, fetching pages as needed.
+    pub fn list_stream(
+        &self,
+        query: CalendarListQuery,
+    ) -> impl Stream<Item = ClientResult<CalendarListItem>> {
+        pagination::items(self.list_pages(query))
+    }
+
+    /// Lazily stream the pages of calendars matching the query.
+    pub fn list_pages(
+        &self,
+        query: CalendarListQuery,
+    ) -> impl Stream<Item = ClientResult<CalendarList>> {
+        let mut target = CalendarList::default();
+        query.apply(&mut target);
+        if let Some(fields) = &self.1 {
+            target.add_query("fields".to_string(), list_mask(fields));
+        }
+
+        pagination::pages(self.0.clone(), move |token| {
+            with_page_token(target.clone(), token)
+        })
+    }
+
+    /// Fetch the calendars that were added, changed or removed since `sync_token`. Without a
+    /// token, or when the server expired it, the whole calendar list (hidden calendars included)
+    /// is fetched instead and `SyncResult::full_resync` is set. Keep
+    /// `SyncResult::next_sync_token` for the next call.
+    pub async fn sync(
+        &self,
+        sync_token: Option<String>,
+    ) -> ClientResult<SyncResult<CalendarListItem>> {
+        let mut target = CalendarList::default();
+        if let Some(fields) = &self.1 {
+            target.add_query("fields".to_string(), list_mask(fields));
+        }
+
+        sync::sync::<_, CalendarList>(
+            self.0.clone(),
+            |sync_token, page_token| {
+                let mut cl = with_page_token(target.clone(), page_token);
+                match sync_token {
+                    Some(token) => cl.add_query("syncToken".to_string(), token),
+                    None => cl.add_query("showHidden".to_string(), true.to_string()),
+                }
+                cl
+            },
+            sync_token,
+        )
+        .await
+    }
+
+    /// Watch the calendar list for changes matching the query. The returned channel carries the
+    /// resource id and expiration set by the server.
+    pub async fn watch(&self, query: CalendarListQuery, channel: Channel) -> ClientResult<Channel> {
+        let target = ChannelRequest::new(
+            "users/me/calendarList/watch".to_string(),
+            query.params(),
+            channel,
+        );
+        Ok(self.0.post(None, target).await?.json().await?)
+    }
+
+    /// List the projections of every calendar.
+    pub async fn list_as<P: Projection>(
+        &self,
+        hidden: bool,
+        access_role: CalendarAccessRole,
+    ) -> ClientResult<Vec<P>> {
+        let mut target = CalendarList::default();
+        CalendarListQuery::new()
+            .min_access_role(access_role)
+            .show_hidden(hidden)
+            .apply(&mut target);
+        target.add_query("fields".to_string(), items_mask(P::FIELDS));
+
+        let pages = pagination::pages::<_, Items<P>>(self.0.clone(), move |token| {
+            with_page_token(target.clone(), token)
+        });
+        pagination::collect_items(pagination::items(pages), None).await
+    }
+
+    /// Apply the field mask of this client, if any, to the request.
+    fn masked(&self, mut item: CalendarListItem) -> CalendarListItem {
+        if let Some(fields) = &self.1 {
+            item.add_query("fields".to_string(), fields.clone());
+        }
+        item
+    }
+}
+
+/// EntryBody is what is written to a calendar list entry: only the preferences the caller set.
+/// Read-only fields such as `accessRole` or `summary` are left out, so that a patch never resets
+/// what it does not mention.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct EntryBody {
+    #[serde(skip)]
+    path: String,
+    #[serde(skip)]
+    query: QueryParams,
+
+    /// Only sent on insert, the ID of the calendar to add.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary_override: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    background_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    foreground_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hidden: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    selected: Option<bool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    default_reminders: Vec<DefaultReminder>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notification_settings: Option<NotificationSettings>,
+}
+
+impl EntryBody {
+    /// The body inserting the entry into the calendar list.
+    fn insert(item: CalendarListItem) -> Self {
+        let mut body = Self::write(item);
+        body.path = String::from("users/me/calendarList");
+        body
+    }
+
+    /// The body updating or patching the entry.
+    fn write(item: CalendarListItem) -> Self {
+        let item = with_color_format(item);
+        Self {
+            path: item.path(None),
+            query: item.query(),
+            id: Some(item.id).filter(|id| !id.is_empty()),
+            summary_override: item.summary_override,
+            color_id: item.color_id,
+            background_color: item.background_color,
+            foreground_color: item.foreground_color,
+            hidden: item.hidden,
+            selected: item.selected,
+            default_reminders: item.default_reminders,
+            notification_settings: item.notification_settings,
+        }
+    }
+}
+
+impl Sendable for EntryBody {
+    fn path(&self, _action: Option<String>) -> String {
+        self.path.clone()
+    }
+
+    fn query(&self) -> QueryParams {
+        self.query.clone()
+    }
+}
+
+/// Ask the server to honour the RGB colors of the entry, if it has any.
+fn with_color_format(mut item: CalendarListItem) -> CalendarListItem {
+    if item.background_color.is_some() || item.foreground_color.is_some() {
+        item.add_query("colorRgbFormat".to_string(), true.to_string());
+    }
+    item
+}
+
+fn with_page_token(mut cl: CalendarList, token: Option<String>) -> CalendarList {
+    if let Some(token) = token {
+        cl.add_query("pageToken".to_string(), token);
     }
+    cl
 }
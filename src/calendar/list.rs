@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
 
 use super::{
-    CalendarAccessRole, ConferenceProperties, DefaultReminder, NotificationSettings, QueryParams,
-    Sendable,
+    progenitor_support, CalendarAccessRole, ConferenceProperties, DefaultReminder,
+    NotificationSettings, Page, QueryParams, Sendable, SyncPage, SyncResult,
 };
 
 /* Google Calendar API: https://developers.google.com/calendar/api/v3/reference/calendarList#resource */
@@ -10,7 +10,7 @@ use super::{
 /// CalendarListItem is a single calendar returned by CalendarList, do not confuse this with
 /// Calendar.
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct CalendarListItem {
     #[serde(
         default = "default_entry_kind",
@@ -21,6 +21,7 @@ pub struct CalendarListItem {
     pub etag: String,
     pub summary: String,
     pub access_role: CalendarAccessRole,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub notification_settings: Option<NotificationSettings>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub location: Option<String>,
@@ -54,12 +55,14 @@ pub struct CalendarListItem {
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct CalendarList {
     #[serde(default = "default_list_kind", skip_serializing_if = "Option::is_none")]
     pub kind: Option<String>,
     pub etag: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_page_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub next_sync_token: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub items: Vec<CalendarListItem>,
@@ -67,15 +70,123 @@ pub struct CalendarList {
     #[serde(skip)]
     query_string: QueryParams,
 }
+impl CalendarListItem {
+    pub fn add_query(&mut self, key: String, value: String) {
+        self.query_string.insert(key, value);
+    }
+}
 impl CalendarList {
     pub fn add_query(&mut self, key: String, value: String) {
         self.query_string.insert(key, value);
     }
 }
 
+impl Page for CalendarList {
+    type Item = CalendarListItem;
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<CalendarListItem> {
+        self.items
+    }
+}
+
+impl SyncPage for CalendarList {
+    fn next_sync_token(&self) -> Option<&str> {
+        self.next_sync_token.as_deref()
+    }
+}
+
+/// CalendarListChanges splits a calendar list sync into what has to be added to, updated in and
+/// removed from a local copy of the calendar list.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct CalendarListChanges<'a> {
+    pub added: Vec<&'a CalendarListItem>,
+    pub updated: Vec<&'a CalendarListItem>,
+    pub removed: Vec<&'a CalendarListItem>,
+}
+
+impl SyncResult<CalendarListItem> {
+    /// Calendars that were subscribed to or changed.
+    pub fn changed(&self) -> impl Iterator<Item = &CalendarListItem> {
+        self.items.iter().filter(|c| c.deleted != Some(true))
+    }
+
+    /// Calendars that were unsubscribed from.
+    pub fn removed(&self) -> impl Iterator<Item = &CalendarListItem> {
+        self.items.iter().filter(|c| c.deleted == Some(true))
+    }
+
+    /// Split the changes according to the calendars already in the local copy, `is_known` tells
+    /// whether a calendar id is in it.
+    pub fn changes(&self, is_known: impl Fn(&str) -> bool) -> CalendarListChanges<'_> {
+        let mut changes = CalendarListChanges::default();
+        for item in &self.items {
+            if item.deleted == Some(true) {
+                changes.removed.push(item);
+            } else if is_known(&item.id) {
+                changes.updated.push(item);
+            } else {
+                changes.added.push(item);
+            }
+        }
+        changes
+    }
+}
+
+/// CalendarListQuery filters the calendars returned when listing the calendar list.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CalendarListQuery {
+    pub min_access_role: Option<CalendarAccessRole>,
+    pub show_hidden: bool,
+    pub show_deleted: bool,
+}
+
+impl CalendarListQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only list calendars the user has at least this role on.
+    pub fn min_access_role(mut self, role: CalendarAccessRole) -> Self {
+        self.min_access_role = Some(role);
+        self
+    }
+
+    /// Also list calendars hidden from the user interface.
+    pub fn show_hidden(mut self, show_hidden: bool) -> Self {
+        self.show_hidden = show_hidden;
+        self
+    }
+
+    /// Also list calendars the user unsubscribed from.
+    pub fn show_deleted(mut self, show_deleted: bool) -> Self {
+        self.show_deleted = show_deleted;
+        self
+    }
+
+    pub(crate) fn apply(&self, cl: &mut CalendarList) {
+        for (key, value) in self.params() {
+            cl.add_query(key, value);
+        }
+    }
+
+    pub(crate) fn params(&self) -> Vec<(String, String)> {
+        let mut params = Vec::new();
+        if let Some(role) = self.min_access_role {
+            params.push(("minAccessRole".to_string(), role.to_string()));
+        }
+        params.push(("showHidden".to_string(), self.show_hidden.to_string()));
+        params.push(("showDeleted".to_string(), self.show_deleted.to_string()));
+        params
+    }
+}
+
 impl Sendable for CalendarListItem {
     fn path(&self, _action: Option<String>) -> String {
-        format!("users/me/calendarList/{}", self.id)
+        progenitor_support::encode_path(&format!("users/me/calendarList/{}", self.id))
     }
 
     fn query(&self) -> QueryParams {
@@ -20,8 +20,12 @@ use super::*;
 pub struct Calendar {
     #[serde(default = "default_kind", skip_serializing_if = "Option::is_none")]
     pub kind: Option<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub id: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub etag: String,
+    /// Required on insert and update, left out of a patch when empty.
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub summary: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
@@ -33,8 +37,17 @@ pub struct Calendar {
     pub conference_properties: Option<ConferenceProperties>,
 }
 impl Sendable for Calendar {
-    fn path(&self, _action: Option<String>) -> String {
-        format!("calendars/{}", self.id)
+    fn path(&self, action: Option<String>) -> String {
+        let mut path = String::from("calendars");
+        if !self.id.is_empty() {
+            path.push('/');
+            path.push_str(&self.id);
+        }
+        if let Some(action) = action.filter(|a| !a.is_empty()) {
+            path.push('/');
+            path.push_str(&action);
+        }
+        progenitor_support::encode_path(&path)
     }
 
     fn query(&self) -> QueryParams {
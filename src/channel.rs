@@ -0,0 +1,141 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::{ClientResult, Endpoints, GCalClient, QueryParams, Sendable};
+
+/* Google API Source: https://developers.google.com/calendar/api/guides/push */
+
+/// Channel is a push notification channel. Google posts a notification to its address whenever
+/// the watched resource changes, until the channel expires or is stopped.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Channel {
+    #[serde(default = "default_kind", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// Identifies the channel, it must be unique for every new channel.
+    pub id: String,
+    /// Identifies the watched resource, set by the server.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub resource_id: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub resource_uri: String,
+    /// Sent back with every notification in the `X-Goog-Channel-Token` header.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Expiration in milliseconds since the epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "String::is_empty")]
+    pub channel_type: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub address: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<bool>,
+}
+
+impl Channel {
+    /// A web hook channel posting its notifications to the HTTPS address.
+    pub fn web_hook(id: impl ToString, address: impl ToString) -> Self {
+        Self {
+            id: id.to_string(),
+            address: address.to_string(),
+            channel_type: "web_hook".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Token sent back with every notification, to tell them apart from forged ones.
+    pub fn token(mut self, token: impl ToString) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// Requested lifetime of the channel, the server may cap it.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.params
+            .insert("ttl".to_string(), ttl.as_secs().to_string());
+        self
+    }
+
+    /// When the channel expires, if the server told.
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp_millis(self.expiration.as_ref()?.parse().ok()?)
+    }
+}
+
+fn default_kind() -> Option<String> {
+    Some("api#channel".to_string())
+}
+
+/// ChannelRequest posts a channel to a `watch` or `stop` endpoint, along with the list parameters
+/// of the watched collection.
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct ChannelRequest {
+    #[serde(flatten)]
+    channel: Channel,
+    #[serde(skip)]
+    path: String,
+    #[serde(skip)]
+    params: Vec<(String, String)>,
+}
+
+impl ChannelRequest {
+    pub(crate) fn new(path: String, params: Vec<(String, String)>, channel: Channel) -> Self {
+        Self {
+            channel,
+            path,
+            params,
+        }
+    }
+}
+
+impl Sendable for ChannelRequest {
+    fn path(&self, _action: Option<String>) -> String {
+        self.path.clone()
+    }
+
+    fn query(&self) -> QueryParams {
+        self.params.iter().cloned().collect()
+    }
+
+    fn url(&self, endpoints: &Endpoints, action: Option<String>) -> ClientResult<Url> {
+        Ok(Url::parse_with_params(
+            &format!("{}/{}", self.base_url(endpoints), self.path(action)),
+            &self.params,
+        )?)
+    }
+}
+
+/// ChannelClient manages push notification channels. Channels are created by the `watch` methods
+/// of the other clients.
+#[derive(Debug, Clone)]
+pub struct ChannelClient(Arc<GCalClient>);
+
+impl ChannelClient {
+    /// Construct a ChannelClient. Requires a Google Calendar Client.
+    pub fn new(client: Arc<GCalClient>) -> Self {
+        Self(client)
+    }
+
+    /// Stop receiving notifications through the channel. Requires its `id` and `resource_id`.
+    pub async fn stop(&self, channel: &Channel) -> ClientResult<()> {
+        let channel = Channel {
+            id: channel.id.clone(),
+            resource_id: channel.resource_id.clone(),
+            token: channel.token.clone(),
+            ..Default::default()
+        };
+        self.0
+            .post(
+                None,
+                ChannelRequest::new("channels/stop".to_string(), Vec::new(), channel),
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -1,12 +1,76 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use reqwest::{
-    header::{HeaderMap, HeaderValue},
-    ClientBuilder, RequestBuilder, Response,
+    header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT},
+    Certificate, ClientBuilder, Method, Proxy, RequestBuilder, Response, StatusCode,
+};
+use tokio::sync::{OnceCell, RwLock};
+use tracing::{field, Instrument};
+
+use super::{
+    retry, telemetry, AclClient, CalendarClient, CalendarListClient, ChannelClient, ClientError,
+    ClientResult, Colors, ColorsClient, Endpoints, EventClient, OAuth, OToken, RateLimiter,
+    RetryEvent, RetryPolicy, Sendable, SettingsClient,
 };
-use tokio::sync::RwLock;
 
-use super::{CalendarListClient, ClientError, ClientResult, EventClient, OAuth, OToken, Sendable};
+/// Conditional is the outcome of a read sent with `If-None-Match`: either the resource changed and
+/// is returned, or the cached copy of the caller is still current.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conditional<T> {
+    Modified(T),
+    NotModified,
+}
+
+impl<T> Conditional<T> {
+    /// Transform the resource, if it changed.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Conditional<U> {
+        match self {
+            Self::Modified(t) => Conditional::Modified(f(t)),
+            Self::NotModified => Conditional::NotModified,
+        }
+    }
+
+    /// The resource, if it changed.
+    pub fn modified(self) -> Option<T> {
+        match self {
+            Self::Modified(t) => Some(t),
+            Self::NotModified => None,
+        }
+    }
+
+    /// The resource if it changed, else the cached copy.
+    pub fn unwrap_or(self, cached: T) -> T {
+        self.modified().unwrap_or(cached)
+    }
+
+    /// Decode the response of a request sent with `If-None-Match`, e.g. through
+    /// `GCalClient::request`, for reads that have no conditional variant.
+    pub async fn from_response(resp: Response) -> ClientResult<Self>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        if resp.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Self::NotModified);
+        }
+        Ok(Self::Modified(resp.json().await?))
+    }
+}
+
+/// Build the headers of a conditional request, e.g. `If-Match` with the etag of a resource.
+pub(crate) fn etag_header(name: HeaderName, etag: &str) -> ClientResult<HeaderMap> {
+    if etag.is_empty() {
+        return Err(ClientError::UnknownError(format!(
+            "Cannot send {} without an etag",
+            name
+        )));
+    }
+    let mut headers = HeaderMap::new();
+    headers.insert(name, HeaderValue::from_str(etag)?);
+    Ok(headers)
+}
 
 /// Client is a Google Calendar client. The access key must have already been fetched and the oauth
 /// negotiation should have already been completed. The client itself only implements HTTP verbs
@@ -16,31 +80,261 @@ use super::{CalendarListClient, ClientError, ClientResult, EventClient, OAuth, O
 pub struct GCalClient {
     client: reqwest::Client,
     headers: Option<HeaderMap<HeaderValue>>,
+    timeout: Option<Duration>,
     token: Arc<RwLock<OToken>>,
     oauth: Option<Arc<OAuth>>,
+    endpoints: Endpoints,
+    retry: RetryPolicy,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    quota_user: Option<String>,
+    colors: Arc<OnceCell<Colors>>,
 
     debug: bool,
 }
 
+/// GCalClientBuilder configures a GCalClient: the HTTP client it uses, the endpoints it talks to
+/// and how it retries and throttles requests. Obtain one with `GCalClient::builder`.
+#[derive(Debug)]
+pub struct GCalClientBuilder {
+    token: OToken,
+    oauth: Option<Arc<OAuth>>,
+    endpoints: Endpoints,
+    retry: RetryPolicy,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    quota_user: Option<String>,
+    headers: HeaderMap<HeaderValue>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<Proxy>,
+    root_certificates: Vec<Certificate>,
+    http_client: Option<reqwest::Client>,
+
+    debug: bool,
+}
+
+impl GCalClientBuilder {
+    pub fn new(token: OToken) -> Self {
+        Self {
+            token,
+            oauth: None,
+            endpoints: Endpoints::default(),
+            retry: RetryPolicy::default(),
+            rate_limiter: None,
+            quota_user: None,
+            headers: HeaderMap::new(),
+            timeout: None,
+            connect_timeout: None,
+            proxy: None,
+            root_certificates: Vec::new(),
+            http_client: None,
+            debug: false,
+        }
+    }
+
+    /// Refresh the token through this OAuth when it expires or gets rejected.
+    pub fn oauth(mut self, oauth: Arc<OAuth>) -> Self {
+        self.oauth = Some(oauth);
+        self
+    }
+
+    /// Send requests to these endpoints instead of Google's.
+    pub fn endpoints(mut self, endpoints: Endpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    pub fn rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Send every request on behalf of the `quotaUser`, see `GCalClient::with_quota_user`.
+    pub fn quota_user(mut self, quota_user: impl ToString) -> Self {
+        self.quota_user = Some(quota_user.to_string());
+        self
+    }
+
+    /// Add a header sent with every request.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Add headers sent with every request.
+    pub fn default_headers(mut self, headers: HeaderMap<HeaderValue>) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    pub fn user_agent(self, user_agent: impl AsRef<str>) -> ClientResult<Self> {
+        let value = HeaderValue::from_str(user_agent.as_ref())?;
+        Ok(self.header(USER_AGENT, value))
+    }
+
+    /// Timeout of a whole request, from connecting until the body has been read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Timeout of the connect phase. Ignored when a custom HTTP client is used.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Send requests through the proxy. Ignored when a custom HTTP client is used.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Trust an additional root certificate. Ignored when a custom HTTP client is used.
+    pub fn add_root_certificate(mut self, certificate: Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Use a pre-built HTTP client, e.g. to share its connection pool with the rest of the
+    /// application.
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Log request bodies at trace level, with personal information and secrets redacted.
+    pub fn debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    pub fn build(self) -> ClientResult<Arc<GCalClient>> {
+        let client = match self.http_client {
+            Some(client) => client,
+            None => {
+                let mut builder = ClientBuilder::new()
+                    .gzip(true)
+                    .https_only(self.endpoints.is_https());
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(proxy) = self.proxy {
+                    builder = builder.proxy(proxy);
+                }
+                for certificate in self.root_certificates {
+                    builder = builder.add_root_certificate(certificate);
+                }
+                builder.build()?
+            }
+        };
+
+        Ok(Arc::new(GCalClient {
+            client,
+            headers: (!self.headers.is_empty()).then_some(self.headers),
+            timeout: self.timeout,
+            token: Arc::new(self.token.into()),
+            oauth: self.oauth,
+            endpoints: self.endpoints,
+            retry: self.retry,
+            rate_limiter: self.rate_limiter,
+            quota_user: self.quota_user,
+            colors: Default::default(),
+            debug: self.debug,
+        }))
+    }
+}
+
 impl GCalClient {
     /// Create a new client. Requires an access key.
     pub fn new(token: OToken, oauth: Option<Arc<OAuth>>) -> ClientResult<Arc<Self>> {
-        let client = ClientBuilder::new().gzip(true).https_only(true).build()?;
+        Self::with_endpoints(token, oauth, Endpoints::default())
+    }
 
-        Ok(Arc::new(Self {
-            client,
-            headers: None,
-            token: Arc::new(token.into()),
-            oauth,
-            debug: false,
-        }))
+    /// Create a new client that sends its requests to the given endpoints instead of Google's.
+    /// Plain HTTP is only allowed when one of the endpoints uses it.
+    pub fn with_endpoints(
+        token: OToken,
+        oauth: Option<Arc<OAuth>>,
+        endpoints: Endpoints,
+    ) -> ClientResult<Arc<Self>> {
+        let mut builder = Self::builder(token).endpoints(endpoints);
+        if let Some(oauth) = oauth {
+            builder = builder.oauth(oauth);
+        }
+        builder.build()
+    }
+
+    /// Configure a new client. Requires an access key.
+    pub fn builder(token: OToken) -> GCalClientBuilder {
+        GCalClientBuilder::new(token)
     }
+
+    /// Replace the retry policy of this client. Other handles to the same client keep theirs.
+    pub fn with_retry_policy(mut self: Arc<Self>, policy: RetryPolicy) -> Arc<Self> {
+        Arc::make_mut(&mut self).retry = policy;
+        self
+    }
+
+    /// Throttle every request of this client through the rate limiter. Other handles to the same
+    /// client keep theirs, handles cloned from the returned one share it. The same limiter can be
+    /// handed to several clients to enforce a project wide quota.
+    pub fn with_rate_limiter(mut self: Arc<Self>, limiter: Arc<RateLimiter>) -> Arc<Self> {
+        Arc::make_mut(&mut self).rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Send every request of this client on behalf of the `quotaUser`, e.g. the end user a sync job
+    /// works for. Google and the rate limiter then count the requests against that user rather
+    /// than the whole project. Other handles to the same client keep theirs.
+    pub fn with_quota_user(mut self: Arc<Self>, quota_user: impl ToString) -> Arc<Self> {
+        Arc::make_mut(&mut self).quota_user = Some(quota_user.to_string());
+        self
+    }
+
+    /// The rate limiter of this client, e.g. to export its bucket states as metrics.
+    pub fn rate_limiter(&self) -> Option<&Arc<RateLimiter>> {
+        self.rate_limiter.as_ref()
+    }
+
+    /// The endpoints this client sends its requests to.
+    pub fn endpoints(&self) -> &Endpoints {
+        &self.endpoints
+    }
+
+    /// The color palettes, fetched once and shared by every client derived from this one.
+    pub(crate) fn colors(&self) -> &OnceCell<Colors> {
+        &self.colors
+    }
+
+    /// A client for the user's calendar list: which calendars they see and their preferences for
+    /// each, see `calendar_resource_client` for the calendars themselves.
     pub fn calendar_client(self: Arc<Self>) -> CalendarListClient {
         CalendarListClient::new(self.clone())
     }
     pub fn event_client(self: Arc<Self>) -> EventClient {
         EventClient::new(self.clone())
     }
+    /// A client for the calendars themselves: creating, renaming, deleting and clearing them.
+    pub fn calendar_resource_client(self: Arc<Self>) -> CalendarClient {
+        CalendarClient::new(self.clone())
+    }
+    pub fn acl_client(self: Arc<Self>) -> AclClient {
+        AclClient::new(self.clone())
+    }
+    pub fn colors_client(self: Arc<Self>) -> ColorsClient {
+        ColorsClient::new(self.clone())
+    }
+    pub fn settings_client(self: Arc<Self>) -> SettingsClient {
+        SettingsClient::new(self.clone())
+    }
+    pub fn channel_client(self: Arc<Self>) -> ChannelClient {
+        ChannelClient::new(self.clone())
+    }
     pub fn clients(self: Arc<Self>) -> (CalendarListClient, EventClient) {
         (
             CalendarListClient::new(self.clone()),
@@ -48,6 +342,8 @@ impl GCalClient {
         )
     }
 
+    /// Clients are shared behind an `Arc` once built, so this cannot be reached in practice.
+    #[deprecated(note = "use `GCalClientBuilder::debug` instead")]
     pub fn set_debug(&mut self) {
         self.debug = true
     }
@@ -58,7 +354,7 @@ impl GCalClient {
         action: Option<String>,
         target: impl Sendable,
     ) -> ClientResult<Response> {
-        self.send(self.client.get(self.get_url("GET", &target, action)?))
+        self.request(Method::GET, action, target, HeaderMap::new())
             .await
     }
 
@@ -68,12 +364,8 @@ impl GCalClient {
         action: Option<String>,
         target: impl Sendable,
     ) -> ClientResult<Response> {
-        self.send(
-            self.client
-                .post(self.get_url("POST", &target, action)?)
-                .body(target.body_bytes()?),
-        )
-        .await
+        self.request(Method::POST, action, target, HeaderMap::new())
+            .await
     }
 
     /// Perform a PUT request.
@@ -82,12 +374,8 @@ impl GCalClient {
         action: Option<String>,
         target: impl Sendable,
     ) -> ClientResult<Response> {
-        self.send(
-            self.client
-                .put(self.get_url("PUT", &target, action)?)
-                .body(target.body_bytes()?),
-        )
-        .await
+        self.request(Method::PUT, action, target, HeaderMap::new())
+            .await
     }
 
     /// Perform a PATCH request.
@@ -96,12 +384,8 @@ impl GCalClient {
         action: Option<String>,
         target: impl Sendable,
     ) -> ClientResult<Response> {
-        self.send(
-            self.client
-                .patch(self.get_url("PATCH", &target, action)?)
-                .body(target.body_bytes()?),
-        )
-        .await
+        self.request(Method::PATCH, action, target, HeaderMap::new())
+            .await
     }
 
     /// Perform a DELETE request.
@@ -110,56 +394,251 @@ impl GCalClient {
         action: Option<String>,
         target: impl Sendable,
     ) -> ClientResult<Response> {
-        self.send(self.client.delete(self.get_url("DELETE", &target, action)?))
+        self.request(Method::DELETE, action, target, HeaderMap::new())
             .await
     }
 
-    async fn send(&self, mut req: RequestBuilder) -> ClientResult<Response> {
-        if let Some(oauth) = &self.oauth {
-            oauth.refresh(&mut *(self.token.write().await)).await?;
+    /// Perform a request with additional headers, such as `If-Match`. The target is sent as the
+    /// body of POST, PUT and PATCH requests.
+    pub async fn request(
+        &self,
+        method: Method,
+        action: Option<String>,
+        target: impl Sendable,
+        headers: HeaderMap,
+    ) -> ClientResult<Response> {
+        let url = target.url(&self.endpoints, action)?;
+        let body = match method {
+            Method::POST | Method::PUT | Method::PATCH => Some(target.body_bytes()?),
+            _ => None,
+        };
+        self.send(method, url, body, headers).await
+    }
+
+    /// Send the request within a tracing span describing it. In debug mode the request body is
+    /// logged at trace level, with personal information redacted.
+    pub(crate) async fn send(
+        &self,
+        method: Method,
+        url: url::Url,
+        body: Option<Vec<u8>>,
+        headers: HeaderMap,
+    ) -> ClientResult<Response> {
+        self.send_counted(method, url, body, headers, 1).await
+    }
+
+    /// Like `send`, for a request that counts as `requests` requests against the quota, e.g. a
+    /// batch of that many operations.
+    pub(crate) async fn send_counted(
+        &self,
+        method: Method,
+        url: url::Url,
+        body: Option<Vec<u8>>,
+        headers: HeaderMap,
+        requests: u32,
+    ) -> ClientResult<Response> {
+        let url = self.quota_url(url);
+        let span = tracing::info_span!(
+            "gcal_request",
+            method = %method,
+            path = url.path(),
+            calendar_id = telemetry::calendar_id(&url),
+            status = field::Empty,
+            latency_ms = field::Empty,
+            retries = field::Empty,
+        );
+        if let Some(body) = body.as_deref().filter(|_| self.debug) {
+            tracing::trace!(parent: &span, body = %telemetry::redact(body), "request body");
         }
-        if let Some(headers) = &self.headers {
-            req = req.headers(headers.clone())
+
+        let started = Instant::now();
+        let result = self
+            .send_with_retries(method, url, body, headers, requests)
+            .instrument(span.clone())
+            .await;
+        span.record("latency_ms", started.elapsed().as_millis() as u64);
+        match &result {
+            Ok(resp) => {
+                span.record("status", resp.status().as_u16());
+            }
+            Err(error) => {
+                if let Some(status) = error.status() {
+                    span.record("status", status);
+                }
+                tracing::debug!(parent: &span, %error, "request failed");
+            }
+        }
+        result
+    }
+
+    /// Send the request through the rate limiter, retrying it according to the retry policy.
+    async fn send_with_retries(
+        &self,
+        method: Method,
+        url: url::Url,
+        body: Option<Vec<u8>>,
+        headers: HeaderMap,
+        requests: u32,
+    ) -> ClientResult<Response> {
+        let quota_user = url
+            .query_pairs()
+            .find(|(k, _)| k == "quotaUser")
+            .map(|(_, v)| v.into_owned());
+
+        let mut attempt = 1;
+        let mut refreshed = false;
+        loop {
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire_many(quota_user.as_deref(), requests).await;
+            }
+            if let Some(oauth) = &self.oauth {
+                oauth
+                    .refresh(&mut *(self.token.write().await))
+                    .await
+                    .map_err(ClientError::from_refresh)?;
+            }
+            let access = self.token.read().await.access.clone();
+
+            let mut req = self.client.request(method.clone(), url.clone());
+            if let Some(headers) = &self.headers {
+                req = req.headers(headers.clone())
+            }
+            req = req.headers(headers.clone());
+            if let Some(timeout) = self.timeout {
+                req = req.timeout(timeout);
+            }
+            if let Some(body) = &body {
+                req = req.body(body.clone());
+            }
+
+            let (error, retry_after) = match self.send_once(req.bearer_auth(&access)).await {
+                Ok(resp) => return Ok(resp),
+                Err(failure) => failure,
+            };
+            if let ClientError::InvalidToken = error {
+                // Replay the request once with a fresh token, the server may have revoked or
+                // rotated the one we had before it expired.
+                if !refreshed && self.refresh_rejected(&access).await? {
+                    refreshed = true;
+                    continue;
+                }
+                return Err(error);
+            }
+            if !self
+                .retry
+                .should_retry(&method, &error, attempt, retry_after)
+            {
+                return Err(error);
+            }
+            let delay = self.retry.delay(attempt, retry_after);
+            self.retry.notify(&RetryEvent {
+                method: &method,
+                url: &url,
+                attempt,
+                delay,
+                error: &error,
+            });
+            tracing::warn!(attempt, ?delay, %error, "retrying request");
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+            tracing::Span::current().record("retries", attempt - 1);
         }
+    }
 
-        let resp = self.set_bearer(req).await.send().await?;
-        if resp.status() != 200 {
+    /// Add the `quotaUser` of this client to the URL, unless the request has its own.
+    fn quota_url(&self, mut url: url::Url) -> url::Url {
+        if let Some(quota_user) = &self.quota_user {
+            if !url.query_pairs().any(|(k, _)| k == "quotaUser") {
+                url.query_pairs_mut().append_pair("quotaUser", quota_user);
+            }
+        }
+        url
+    }
+
+    /// Send the request a single time. Failures carry the `Retry-After` the server asked for.
+    async fn send_once(
+        &self,
+        req: RequestBuilder,
+    ) -> Result<Response, (ClientError, Option<std::time::Duration>)> {
+        let resp = req.send().await.map_err(|e| (e.into(), None))?;
+        if !resp.status().is_success() && resp.status() != StatusCode::NOT_MODIFIED {
             if let Some(header) = resp.headers().get("WWW-Authenticate") {
-                if header
-                    .to_str()?
-                    .starts_with(r#"Bearer error="invalid_token""#)
-                {
-                    return Err(ClientError::InvalidToken);
+                if header.to_str().is_ok_and(rejects_token) {
+                    return Err((ClientError::InvalidToken, None));
                 }
             }
+            let status = resp.status().as_u16();
+            let retry_after = retry::retry_after(resp.headers());
+            let body = resp.bytes().await.map_err(|e| (e.into(), None))?;
+            return Err((ClientError::from_response(status, &body), retry_after));
         }
         Ok(resp)
     }
 
-    fn get_url(
-        &self,
-        method: &str,
-        target: &impl Sendable,
-        action: Option<String>,
-    ) -> ClientResult<url::Url> {
-        let url = target.url(action)?;
-
-        if self.debug {
-            eprintln!(
-                "[{}] {} | {}",
-                method,
-                url,
-                String::from_utf8(target.body_bytes()?).unwrap_or_default()
-            );
+    /// Refresh the token after the server rejected the `rejected` access token. Returns whether
+    /// the request is worth replaying, i.e. a new token is available.
+    async fn refresh_rejected(&self, rejected: &str) -> ClientResult<bool> {
+        let Some(oauth) = &self.oauth else {
+            return Ok(false);
+        };
+        let mut token = self.token.write().await;
+        if token.access != rejected {
+            // Another request refreshed it in the meantime.
+            return Ok(true);
         }
+        if token.refresh.is_none() {
+            return Ok(false);
+        }
+        // A refresh token the endpoint refuses, e.g. a revoked one, cannot help anymore.
+        match oauth.force_refresh(&mut token).await {
+            Ok(()) => Ok(true),
+            Err(error) => match ClientError::from_refresh(error) {
+                error @ ClientError::TokenEndpointUnreachable(_) => Err(error),
+                error => {
+                    tracing::debug!(%error, "failed to refresh a rejected token");
+                    Err(ClientError::InvalidToken)
+                }
+            },
+        }
+    }
+}
 
-        Ok(url)
+/// Whether a `WWW-Authenticate` challenge says the access token was rejected, e.g.
+/// `Bearer realm="https://accounts.google.com/", error="invalid_token"`.
+fn rejects_token(challenge: &str) -> bool {
+    let Some((scheme, params)) = challenge.trim().split_once(' ') else {
+        return false;
+    };
+    scheme.eq_ignore_ascii_case("Bearer")
+        && params
+            .split(',')
+            .filter_map(|param| param.trim().split_once('='))
+            .any(|(key, value)| {
+                key.trim().eq_ignore_ascii_case("error")
+                    && value.trim().trim_matches('"') == "invalid_token"
+            })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_rejected_by_google() {
+        assert!(rejects_token(
+            r#"Bearer realm="https://accounts.google.com/", error="invalid_token""#
+        ));
+        assert!(rejects_token(r#"Bearer error="invalid_token""#));
     }
 
-    async fn set_bearer(&self, req: RequestBuilder) -> RequestBuilder {
-        req.header(
-            "Authorization",
-            format!("Bearer {}", self.token.read().await.access),
-        )
+    #[test]
+    fn other_challenges() {
+        assert!(!rejects_token(
+            r#"Bearer realm="https://accounts.google.com/""#
+        ));
+        assert!(!rejects_token(
+            r#"Bearer realm="https://accounts.google.com/", error="insufficient_scope""#
+        ));
+        assert!(!rejects_token(r#"Basic realm="invalid_token""#));
     }
 }
@@ -0,0 +1,100 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use super::{CalendarListItem, ClientResult, Event, GCalClient, QueryParams, Sendable};
+
+/* Google Calendar API: https://developers.google.com/calendar/api/v3/reference/colors#resource */
+
+/// ColorDefinition is a pair of RGB colors, e.g. `#ac725e`.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ColorDefinition {
+    pub background: String,
+    pub foreground: String,
+}
+
+/// Colors holds the palettes `color_id`s refer to, by ID.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Colors {
+    #[serde(default = "default_kind", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    pub updated: String,
+    /// Palette of `CalendarListItem::color_id`.
+    pub calendar: BTreeMap<String, ColorDefinition>,
+    /// Palette of `Event::color_id`.
+    pub event: BTreeMap<String, ColorDefinition>,
+}
+
+impl Colors {
+    /// The colors an event is displayed with: its own color, else the color of its calendar, else
+    /// the RGB colors set on the calendar list entry. `calendar` is the entry of the calendar the
+    /// event belongs to.
+    pub fn resolve(
+        &self,
+        event: &Event,
+        calendar: Option<&CalendarListItem>,
+    ) -> Option<ColorDefinition> {
+        if let Some(colors) = event.color_id.as_ref().and_then(|id| self.event.get(id)) {
+            return Some(colors.clone());
+        }
+        let calendar = calendar?;
+        if let Some(colors) = calendar
+            .color_id
+            .as_ref()
+            .and_then(|id| self.calendar.get(id))
+        {
+            return Some(colors.clone());
+        }
+        Some(ColorDefinition {
+            background: calendar.background_color.clone()?,
+            foreground: calendar.foreground_color.clone()?,
+        })
+    }
+}
+
+impl Sendable for Colors {
+    fn path(&self, _action: Option<String>) -> String {
+        String::from("colors")
+    }
+
+    fn query(&self) -> QueryParams {
+        Default::default()
+    }
+}
+
+fn default_kind() -> Option<String> {
+    Some("calendar#colors".to_string())
+}
+
+/// ColorsClient gives access to the color palettes. Requires a Google Calendar client.
+#[derive(Debug, Clone)]
+pub struct ColorsClient(Arc<GCalClient>);
+
+impl ColorsClient {
+    /// Construct a ColorsClient. Requires a Google Calendar Client.
+    pub fn new(client: Arc<GCalClient>) -> Self {
+        Self(client)
+    }
+
+    /// Get the color palettes. They are only fetched the first time, the Google Calendar client
+    /// caches them afterwards.
+    pub async fn get(&self) -> ClientResult<&Colors> {
+        self.0
+            .colors()
+            .get_or_try_init(|| async {
+                Ok(self.0.get(None, Colors::default()).await?.json().await?)
+            })
+            .await
+    }
+
+    /// The colors an event is displayed with, see `Colors::resolve`.
+    pub async fn resolve(
+        &self,
+        event: &Event,
+        calendar: Option<&CalendarListItem>,
+    ) -> ClientResult<Option<ColorDefinition>> {
+        Ok(self.get().await?.resolve(event, calendar))
+    }
+}
@@ -1,19 +1,169 @@
+use oauth2::{basic::BasicRequestTokenError, reqwest::AsyncHttpClientError, RequestTokenError};
+use serde::Deserialize;
 use thiserror::Error;
 
 pub type ClientResult<T, E = ClientError> = std::result::Result<T, E>;
 
-/// ClientError provides a mechanism to determine when the access token has expired. All other
-/// errors will be encapsulated by UnknownError.
+/// ClientError provides a mechanism to determine when the access token has expired and why Google
+/// rejected a request. Errors that do not come from the API will be encapsulated by UnknownError.
 #[derive(Debug, Error)]
 pub enum ClientError {
     #[error("Invalid Access Token")]
     InvalidToken,
     #[error("HTTP Error: {0}")]
     HttpError(reqwest::Error),
+    #[error("Bad Request: {0}")]
+    BadRequest(ApiError),
+    #[error("Forbidden: {0}")]
+    Forbidden(ApiError),
+    #[error("Not Found: {0}")]
+    NotFound(ApiError),
+    #[error("Gone: {0}")]
+    Gone(ApiError),
+    #[error("Precondition Failed: {0}")]
+    PreconditionFailed(ApiError),
+    #[error("Rate Limited: {0}")]
+    RateLimited(ApiError),
+    #[error("Quota Exceeded: {0}")]
+    QuotaExceeded(ApiError),
+    #[error("Server Error: {0}")]
+    ServerError(ApiError),
+    #[error("API Error: {0}")]
+    ApiError(ApiError),
+    #[error("Token Endpoint Unreachable: {0}")]
+    TokenEndpointUnreachable(String),
     #[error("Unknown Error: {0}")]
     UnknownError(String),
 }
 
+impl ClientError {
+    /// Build the error matching a non-successful response from its status code and body.
+    pub fn from_response(status: u16, body: &[u8]) -> Self {
+        let error = ApiError::from_body(status, body);
+        match (status, error.reason()) {
+            (400, _) => Self::BadRequest(error),
+            (403 | 429, Some("rateLimitExceeded" | "userRateLimitExceeded")) | (429, _) => {
+                Self::RateLimited(error)
+            }
+            (403, Some("quotaExceeded" | "dailyLimitExceeded")) => Self::QuotaExceeded(error),
+            (403, _) => Self::Forbidden(error),
+            (404, _) => Self::NotFound(error),
+            (410, _) => Self::Gone(error),
+            (412, _) => Self::PreconditionFailed(error),
+            (500..=599, _) => Self::ServerError(error),
+            _ => Self::ApiError(error),
+        }
+    }
+
+    /// Build the error of a failed token refresh. Failing to reach the token endpoint, e.g. while
+    /// offline, is kept apart from the endpoint refusing the refresh token.
+    pub(crate) fn from_refresh(error: anyhow::Error) -> Self {
+        match error.downcast_ref::<BasicRequestTokenError<AsyncHttpClientError>>() {
+            Some(RequestTokenError::Request(_)) => {
+                Self::TokenEndpointUnreachable(format!("{:#}", error))
+            }
+            _ => error.into(),
+        }
+    }
+
+    /// The error body returned by Google, if this error came from the API.
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            Self::BadRequest(e)
+            | Self::Forbidden(e)
+            | Self::NotFound(e)
+            | Self::Gone(e)
+            | Self::PreconditionFailed(e)
+            | Self::RateLimited(e)
+            | Self::QuotaExceeded(e)
+            | Self::ServerError(e)
+            | Self::ApiError(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// The HTTP status code of the response that caused this error, if any.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::InvalidToken => Some(401),
+            Self::HttpError(e) => e.status().map(|s| s.as_u16()),
+            _ => self.api_error().map(|e| e.code),
+        }
+    }
+
+    /// The first reason Google gave for this error, e.g. `rateLimitExceeded`.
+    pub fn reason(&self) -> Option<&str> {
+        self.api_error().and_then(ApiError::reason)
+    }
+}
+
+/* Google API Source: https://developers.google.com/calendar/api/guides/errors */
+
+/// ApiError is the error body Google sends back with a failed request.
+#[derive(Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct ApiError {
+    pub code: u16,
+    pub message: String,
+    pub errors: Vec<ApiErrorDetail>,
+}
+
+/// ApiErrorDetail is a single entry of `ApiError::errors`. For bad requests, `location` names the
+/// offending parameter or field.
+#[derive(Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ApiErrorDetail {
+    pub domain: String,
+    pub reason: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub location_type: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ApiErrorBody {
+    error: ApiError,
+}
+
+impl ApiError {
+    /// Parse a `{"error": {...}}` body. Bodies that are not in this shape are kept verbatim as the
+    /// message.
+    pub fn from_body(status: u16, body: &[u8]) -> Self {
+        match serde_json::from_slice::<ApiErrorBody>(body) {
+            Ok(ApiErrorBody { mut error }) => {
+                if error.code == 0 {
+                    error.code = status;
+                }
+                error
+            }
+            Err(_) => Self {
+                code: status,
+                message: String::from_utf8_lossy(body).into_owned(),
+                errors: Vec::new(),
+            },
+        }
+    }
+
+    /// The reason of the first error detail.
+    pub fn reason(&self) -> Option<&str> {
+        self.errors.first().map(|e| e.reason.as_str())
+    }
+
+    /// The fields or parameters the error details point at.
+    pub fn locations(&self) -> impl Iterator<Item = &str> {
+        self.errors.iter().filter_map(|e| e.location.as_deref())
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.reason() {
+            Some(reason) => write!(f, "{} {}: {}", self.code, reason, self.message),
+            None => write!(f, "{}: {}", self.code, self.message),
+        }
+    }
+}
+
 impl From<anyhow::Error> for ClientError {
     fn from(value: anyhow::Error) -> Self {
         Self::UnknownError(value.to_string())
@@ -26,6 +176,12 @@ impl From<serde_json::Error> for ClientError {
     }
 }
 
+impl From<std::io::Error> for ClientError {
+    fn from(value: std::io::Error) -> Self {
+        Self::UnknownError(value.to_string())
+    }
+}
+
 impl From<url::ParseError> for ClientError {
     fn from(value: url::ParseError) -> Self {
         Self::UnknownError(value.to_string())
@@ -34,6 +190,12 @@ impl From<url::ParseError> for ClientError {
 
 impl From<reqwest::Error> for ClientError {
     fn from(value: reqwest::Error) -> Self {
+        Self::HttpError(value)
+    }
+}
+
+impl From<reqwest::header::InvalidHeaderValue> for ClientError {
+    fn from(value: reqwest::header::InvalidHeaderValue) -> Self {
         Self::UnknownError(value.to_string())
     }
 }
@@ -43,3 +205,99 @@ impl From<reqwest::header::ToStrError> for ClientError {
         Self::UnknownError(value.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(code: u16, reason: &str) -> Vec<u8> {
+        serde_json::json!({
+            "error": {
+                "code": code,
+                "message": "Refused",
+                "errors": [{"domain": "usageLimits", "reason": reason, "message": "Refused"}],
+            }
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    #[test]
+    fn forbidden_by_reason() {
+        assert!(matches!(
+            ClientError::from_response(403, &body(403, "rateLimitExceeded")),
+            ClientError::RateLimited(_)
+        ));
+        assert!(matches!(
+            ClientError::from_response(403, &body(403, "userRateLimitExceeded")),
+            ClientError::RateLimited(_)
+        ));
+        assert!(matches!(
+            ClientError::from_response(403, &body(403, "quotaExceeded")),
+            ClientError::QuotaExceeded(_)
+        ));
+        assert!(matches!(
+            ClientError::from_response(403, &body(403, "dailyLimitExceeded")),
+            ClientError::QuotaExceeded(_)
+        ));
+        assert!(matches!(
+            ClientError::from_response(403, &body(403, "forbiddenForNonOrganizer")),
+            ClientError::Forbidden(_)
+        ));
+    }
+
+    #[test]
+    fn rate_limited_without_body() {
+        let error = ClientError::from_response(429, b"");
+        assert!(matches!(error, ClientError::RateLimited(_)));
+        assert_eq!(error.status(), Some(429));
+        assert_eq!(error.reason(), None);
+    }
+
+    #[test]
+    fn by_status() {
+        assert!(matches!(
+            ClientError::from_response(400, &body(400, "invalid")),
+            ClientError::BadRequest(_)
+        ));
+        assert!(matches!(
+            ClientError::from_response(404, &body(404, "notFound")),
+            ClientError::NotFound(_)
+        ));
+        assert!(matches!(
+            ClientError::from_response(410, &body(410, "fullSyncRequired")),
+            ClientError::Gone(_)
+        ));
+        assert!(matches!(
+            ClientError::from_response(412, &body(412, "conditionNotMet")),
+            ClientError::PreconditionFailed(_)
+        ));
+        assert!(matches!(
+            ClientError::from_response(409, &body(409, "duplicate")),
+            ClientError::ApiError(_)
+        ));
+        for status in [500, 502, 503, 504] {
+            let error = ClientError::from_response(status, &body(status, "backendError"));
+            assert!(matches!(error, ClientError::ServerError(_)), "{}", status);
+            assert!(error.is_transient());
+        }
+    }
+
+    #[test]
+    fn decoded_details() {
+        let error = ClientError::from_response(410, &body(410, "fullSyncRequired"));
+        assert_eq!(error.status(), Some(410));
+        assert_eq!(error.reason(), Some("fullSyncRequired"));
+        assert_eq!(error.api_error().unwrap().message, "Refused");
+    }
+
+    #[test]
+    fn body_that_is_not_json() {
+        let error = ClientError::from_response(502, b"<html>Bad Gateway</html>");
+        assert!(matches!(error, ClientError::ServerError(_)));
+        let api_error = error.api_error().unwrap();
+        assert_eq!(api_error.code, 502);
+        assert_eq!(api_error.message, "<html>Bad Gateway</html>");
+        assert_eq!(api_error.reason(), None);
+    }
+}
@@ -0,0 +1,48 @@
+use futures::{stream, StreamExt};
+
+use super::{ClientError, Event, EventClient};
+
+/// Agenda is the merged view of the events of several calendars.
+#[derive(Debug, Default)]
+pub struct Agenda {
+    /// Events of every calendar that could be fetched, by start time.
+    pub events: Vec<Event>,
+    /// Calendars that could not be fetched, along with the reason.
+    pub failures: Vec<(String, ClientError)>,
+}
+
+impl EventClient {
+    /// Fetch the events of every calendar between the start and end times, at most `concurrency`
+    /// calendars at a time. A calendar that fails is reported in `Agenda::failures` instead of
+    /// aborting the others.
+    pub async fn agenda(
+        &self,
+        calendar_ids: impl IntoIterator<Item = String>,
+        start_time: chrono::DateTime<chrono::Local>,
+        end_time: chrono::DateTime<chrono::Local>,
+        concurrency: usize,
+    ) -> Agenda {
+        let results = stream::iter(calendar_ids)
+            .map(|calendar_id| async move {
+                let events = self.list(calendar_id.clone(), start_time, end_time).await;
+                (calendar_id, events)
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut agenda = Agenda::default();
+        for (calendar_id, result) in results {
+            match result {
+                Ok(events) => agenda.events.extend(events),
+                Err(error) => agenda.failures.push((calendar_id, error)),
+            }
+        }
+        agenda.events.sort_by_cached_key(|e| {
+            // Events without a start time go last.
+            let start = e.start.to_datetime();
+            (start.is_none(), start, e.calendar_id.clone(), e.id.clone())
+        });
+        agenda
+    }
+}
@@ -1,16 +1,42 @@
 use std::sync::Arc;
 
-use super::{ClientResult, Event, Events, GCalClient, SendUpdates};
+use futures::{Stream, TryStreamExt};
+use reqwest::{
+    header::{IF_MATCH, IF_NONE_MATCH},
+    Method,
+};
+
+use super::{
+    channel::ChannelRequest,
+    etag_header,
+    fields::{items_mask, list_mask, Items},
+    pagination,
+    query::EventListRequest,
+    sync, Channel, ClientResult, Conditional, Event, EventListQuery, EventOrderBy, Events,
+    GCalClient, Projection, SendUpdates, SyncResult,
+};
 
 /// EventClient is the method of managing events from a specific calendar. Requires a Google
 /// Calendar client.
 #[derive(Debug, Clone)]
-pub struct EventClient(Arc<GCalClient>);
+pub struct EventClient(Arc<GCalClient>, Option<String>);
 
 impl EventClient {
     /// Construct a new EventClient. Requires a Google Calendar Client.
     pub fn new(client: Arc<GCalClient>) -> Self {
-        Self(client)
+        Self(client, None)
+    }
+
+    /// Only request the fields in the mask from now on, e.g. `id,summary,start,end`. The mask
+    /// selects the fields of each event: lists wrap it as `items(...)` and keep the page and sync
+    /// tokens. Fields that are left out take their default values.
+    pub fn with_fields(&self, fields: impl ToString) -> Self {
+        Self(self.0.clone(), Some(fields.to_string()))
+    }
+
+    /// The same client without its field mask, for callers that need whole events.
+    pub(crate) fn unmasked(&self) -> Self {
+        Self(self.0.clone(), None)
     }
 
     /// Delete the event.
@@ -19,13 +45,60 @@ impl EventClient {
         Ok(())
     }
 
+    /// Delete the event, unless it changed on the server since it was fetched. A concurrent change
+    /// fails with `ClientError::PreconditionFailed`.
+    pub async fn delete_if_match(&self, event: Event) -> ClientResult<()> {
+        let headers = etag_header(IF_MATCH, &event.etag)?;
+        self.0.request(Method::DELETE, None, event, headers).await?;
+        Ok(())
+    }
+
     /// Get an event by ID.
     pub async fn get(&self, calendar_id: String, event_id: String) -> ClientResult<Event> {
-        let event = Event {
+        let event = self.masked(Event {
+            id: event_id,
+            calendar_id,
+            ..Default::default()
+        });
+        Ok(self.0.get(None, event).await?.json().await?)
+    }
+
+    /// Get an event by ID, unless it still matches the etag of the cached copy.
+    pub async fn get_if_none_match(
+        &self,
+        calendar_id: String,
+        event_id: String,
+        etag: &str,
+    ) -> ClientResult<Conditional<Event>> {
+        let event = self.masked(Event {
+            id: event_id,
+            calendar_id: calendar_id.clone(),
+            ..Default::default()
+        });
+        let resp = self
+            .0
+            .request(Method::GET, None, event, etag_header(IF_NONE_MATCH, etag)?)
+            .await?;
+        Ok(Conditional::<Event>::from_response(resp)
+            .await?
+            .map(|mut event| {
+                event.calendar_id = calendar_id;
+                event
+            }))
+    }
+
+    /// Get the projection of an event by ID.
+    pub async fn get_as<P: Projection>(
+        &self,
+        calendar_id: String,
+        event_id: String,
+    ) -> ClientResult<P> {
+        let mut event = Event {
             id: event_id,
             calendar_id,
             ..Default::default()
         };
+        event.add_query("fields".to_string(), P::FIELDS.to_string());
         Ok(self.0.get(None, event).await?.json().await?)
     }
 
@@ -34,28 +107,32 @@ impl EventClient {
     pub async fn import(&self, event: Event) -> ClientResult<Event> {
         Ok(self
             .0
-            .post(Some("import".to_string()), event)
+            .post(Some("import".to_string()), self.masked(event))
             .await?
             .json()
             .await?)
     }
 
     /// Insert an event. See the Google Calendar documentation for the differences between import
-    /// and insert.
+    /// and insert. The server assigns the ID unless the event carries one, which must be made of
+    /// base32hex characters; inserting the same ID twice fails with a 409.
     pub async fn insert(&self, mut event: Event) -> ClientResult<Event> {
         if !event.attachments.is_empty() {
             event.add_query("supportsAttachments".to_string(), "true".to_string());
         }
         Ok(self
             .0
-            .post(Some(String::new()), event)
+            .post(Some(String::new()), self.masked(event))
             .await?
             .json()
             .await?)
     }
 
     /// Retrieve all instances for a recurring event.
-    pub async fn instances(&self, event: Event) -> ClientResult<Events> {
+    pub async fn instances(&self, mut event: Event) -> ClientResult<Events> {
+        if let Some(fields) = &self.1 {
+            event.add_query("fields".to_string(), list_mask(fields));
+        }
         Ok(self
             .0
             .get(Some("instances".to_string()), event)
@@ -64,32 +141,215 @@ impl EventClient {
             .await?)
     }
 
-    /// List events between the start and end times.
+    /// Retrieve the projections of all instances for a recurring event.
+    pub async fn instances_as<P: Projection>(&self, mut event: Event) -> ClientResult<Vec<P>> {
+        event.add_query("fields".to_string(), items_mask(P::FIELDS));
+        Ok(self
+            .0
+            .get(Some("instances".to_string()), event)
+            .await?
+            .json::<Items<P>>()
+            .await?
+            .items)
+    }
+
+    /// List every event between the start and end times, following all pages.
     pub async fn list(
         &self,
         calendar_id: String,
         start_time: chrono::DateTime<chrono::Local>,
         end_time: chrono::DateTime<chrono::Local>,
     ) -> ClientResult<Vec<Event>> {
-        let mut event = Event {
-            calendar_id: calendar_id.clone(),
-            ..Default::default()
-        };
-        event.add_query("timeMin".to_string(), start_time.to_rfc3339());
-        event.add_query("timeMax".to_string(), end_time.to_rfc3339());
-        event.add_query("singleEvents".to_string(), "true".to_string());
-        event.add_query("orderBy".to_string(), "startTime".to_string());
+        self.query(calendar_id, window_query(start_time, end_time))
+            .await
+    }
+
+    /// List at most `max_items` events between the start and end times. Pages past the cap are not
+    /// fetched.
+    pub async fn list_up_to(
+        &self,
+        calendar_id: String,
+        start_time: chrono::DateTime<chrono::Local>,
+        end_time: chrono::DateTime<chrono::Local>,
+        max_items: usize,
+    ) -> ClientResult<Vec<Event>> {
+        pagination::collect_items(
+            self.query_stream(calendar_id, window_query(start_time, end_time)),
+            Some(max_items),
+        )
+        .await
+    }
+
+    /// Lazily stream the events between the start and end times, fetching pages as needed.
+    pub fn list_stream(
+        &self,
+        calendar_id: String,
+        start_time: chrono::DateTime<chrono::Local>,
+        end_time: chrono::DateTime<chrono::Local>,
+    ) -> impl Stream<Item = ClientResult<Event>> {
+        self.query_stream(calendar_id, window_query(start_time, end_time))
+    }
+
+    /// Lazily stream the pages of events between the start and end times.
+    pub fn list_pages(
+        &self,
+        calendar_id: String,
+        start_time: chrono::DateTime<chrono::Local>,
+        end_time: chrono::DateTime<chrono::Local>,
+    ) -> impl Stream<Item = ClientResult<Events>> {
+        self.query_pages(calendar_id, window_query(start_time, end_time))
+    }
+
+    /// List the projections of every event between the start and end times.
+    pub async fn list_as<P: Projection>(
+        &self,
+        calendar_id: String,
+        start_time: chrono::DateTime<chrono::Local>,
+        end_time: chrono::DateTime<chrono::Local>,
+    ) -> ClientResult<Vec<P>> {
+        let mut target = EventListRequest::list(&calendar_id, &window_query(start_time, end_time));
+        target.add_query("fields".to_string(), items_mask(P::FIELDS));
 
-        let mut events = self
+        let pages = pagination::pages::<_, Items<P>>(self.0.clone(), move |token| {
+            with_page_token(target.clone(), token)
+        });
+        pagination::collect_items(pagination::items(pages), None).await
+    }
+
+    /// List every event matching the query, following all pages.
+    pub async fn query(
+        &self,
+        calendar_id: String,
+        query: EventListQuery,
+    ) -> ClientResult<Vec<Event>> {
+        pagination::collect_items(self.query_stream(calendar_id, query), None).await
+    }
+
+    /// Lazily stream the events matching the query, fetching pages as needed.
+    pub fn query_stream(
+        &self,
+        calendar_id: String,
+        query: EventListQuery,
+    ) -> impl Stream<Item = ClientResult<Event>> {
+        pagination::items(self.query_pages(calendar_id, query))
+    }
+
+    /// Lazily stream the pages of events matching the query.
+    pub fn query_pages(
+        &self,
+        calendar_id: String,
+        query: EventListQuery,
+    ) -> impl Stream<Item = ClientResult<Events>> {
+        let target = EventListRequest::list(&calendar_id, &query);
+        self.pages(calendar_id, target)
+    }
+
+    /// Fetch the first page of events matching the query, unless it is unchanged since the page
+    /// with the etag was fetched, see `Events::etag`. Follow `Events::next_page_token` with
+    /// `query_pages` when it changed.
+    pub async fn query_if_none_match(
+        &self,
+        calendar_id: String,
+        query: EventListQuery,
+        etag: &str,
+    ) -> ClientResult<Conditional<Events>> {
+        let target = self.masked_list(EventListRequest::list(&calendar_id, &query));
+        let resp = self
             .0
-            .get(None, event)
-            .await
-            .expect("Fail here 1")
-            .json::<Events>()
+            .request(Method::GET, None, target, etag_header(IF_NONE_MATCH, etag)?)
+            .await?;
+        Ok(Conditional::<Events>::from_response(resp)
+            .await?
+            .map(|mut events| {
+                events.add_calendar(calendar_id);
+                events
+            }))
+    }
+
+    /// List every instance of a recurring event matching the query, following all pages.
+    pub async fn query_instances(
+        &self,
+        calendar_id: String,
+        event_id: String,
+        query: EventListQuery,
+    ) -> ClientResult<Vec<Event>> {
+        pagination::collect_items(
+            self.query_instances_stream(calendar_id, event_id, query),
+            None,
+        )
+        .await
+    }
+
+    /// Lazily stream the instances of a recurring event matching the query.
+    pub fn query_instances_stream(
+        &self,
+        calendar_id: String,
+        event_id: String,
+        query: EventListQuery,
+    ) -> impl Stream<Item = ClientResult<Event>> {
+        let target = EventListRequest::instances(&calendar_id, &event_id, &query);
+        pagination::items(self.pages(calendar_id, target))
+    }
+
+    /// Fetch the events that changed since `sync_token`, cancelled ones included. Without a token,
+    /// or when the server expired it, every event of the calendar is fetched instead and
+    /// `SyncResult::full_resync` is set. Keep `SyncResult::next_sync_token` for the next call.
+    pub async fn sync(
+        &self,
+        calendar_id: String,
+        sync_token: Option<String>,
+    ) -> ClientResult<SyncResult<Event>> {
+        self.sync_query(calendar_id, EventListQuery::new(), sync_token)
             .await
-            .expect("Fail 2");
-        events.add_calendar(calendar_id);
-        Ok(events.items)
+    }
+
+    /// Like `sync`, with list parameters that the server accepts along with a sync token, e.g.
+    /// `single_events` to sync the instances of recurring events rather than their series. The
+    /// same query must be used for every call sharing a sync token.
+    pub async fn sync_query(
+        &self,
+        calendar_id: String,
+        query: EventListQuery,
+        sync_token: Option<String>,
+    ) -> ClientResult<SyncResult<Event>> {
+        let target = self.masked_list(EventListRequest::list(&calendar_id, &query));
+
+        let mut result = sync::sync::<_, Events>(
+            self.0.clone(),
+            |sync_token, page_token| {
+                let mut request = with_page_token(target.clone(), page_token);
+                if let Some(token) = sync_token {
+                    request.add_query("syncToken".to_string(), token);
+                }
+                request
+            },
+            sync_token,
+        )
+        .await?;
+        result
+            .items
+            .iter_mut()
+            .for_each(|e| e.calendar_id = calendar_id.clone());
+        Ok(result)
+    }
+
+    /// Watch the events of a calendar matching the query for changes. The returned channel carries
+    /// the resource id and expiration set by the server.
+    pub async fn watch(
+        &self,
+        calendar_id: String,
+        query: EventListQuery,
+        channel: Channel,
+    ) -> ClientResult<Channel> {
+        let target = ChannelRequest::new(
+            super::progenitor_support::encode_path(&format!(
+                "calendars/{}/events/watch",
+                calendar_id
+            )),
+            query.params(),
+            channel,
+        );
+        Ok(self.0.post(None, target).await?.json().await?)
     }
 
     /// Move event to another destination calendar_id.
@@ -111,7 +371,7 @@ impl EventClient {
 
     /// Add an event with the summary.
     pub async fn add(&self, text: String) -> ClientResult<Event> {
-        let mut event = Event::default();
+        let mut event = self.masked(Event::default());
         event.add_query("text".to_string(), text);
 
         Ok(self
@@ -124,6 +384,69 @@ impl EventClient {
 
     /// Update an event.
     pub async fn update(&self, event: Event) -> ClientResult<Event> {
-        Ok(self.0.put(None, event).await?.json().await?)
+        Ok(self.0.put(None, self.masked(event)).await?.json().await?)
+    }
+
+    /// Update an event, unless it changed on the server since it was fetched. A concurrent change
+    /// fails with `ClientError::PreconditionFailed` instead of being overwritten.
+    pub async fn update_if_match(&self, event: Event) -> ClientResult<Event> {
+        let headers = etag_header(IF_MATCH, &event.etag)?;
+        Ok(self
+            .0
+            .request(Method::PUT, None, self.masked(event), headers)
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// Lazily fetch the pages of an event collection, applying the field mask of this client.
+    fn pages(
+        &self,
+        calendar_id: String,
+        target: EventListRequest,
+    ) -> impl Stream<Item = ClientResult<Events>> {
+        let target = self.masked_list(target);
+        pagination::pages(self.0.clone(), move |token| {
+            with_page_token(target.clone(), token)
+        })
+        .map_ok(move |mut events: Events| {
+            events.add_calendar(calendar_id.clone());
+            events
+        })
+    }
+
+    /// Apply the field mask of this client, if any, to the items of the list request.
+    fn masked_list(&self, mut target: EventListRequest) -> EventListRequest {
+        if let Some(fields) = &self.1 {
+            target.add_query("fields".to_string(), list_mask(fields));
+        }
+        target
+    }
+
+    /// Apply the field mask of this client, if any, to the request.
+    fn masked(&self, mut event: Event) -> Event {
+        if let Some(fields) = &self.1 {
+            event.add_query("fields".to_string(), fields.clone());
+        }
+        event
+    }
+}
+
+/// The query `list` always used: the expanded events of a time window, by start time.
+fn window_query(
+    start_time: chrono::DateTime<chrono::Local>,
+    end_time: chrono::DateTime<chrono::Local>,
+) -> EventListQuery {
+    EventListQuery::new()
+        .time_min(start_time)
+        .time_max(end_time)
+        .single_events(true)
+        .order_by(EventOrderBy::StartTime)
+}
+
+fn with_page_token(mut target: EventListRequest, token: Option<String>) -> EventListRequest {
+    if let Some(token) = token {
+        target.add_query("pageToken".to_string(), token);
     }
+    target
 }
@@ -5,6 +5,12 @@ use serde::{Deserialize, Serialize};
 mod client;
 pub use client::EventClient;
 
+mod query;
+pub use query::{EventListQuery, EventOrderBy};
+
+mod agenda;
+pub use agenda::Agenda;
+
 pub mod types;
 use types::*;
 
@@ -31,6 +37,8 @@ pub struct Events {
     pub default_reminders: Vec<DefaultReminder>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub next_page_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_sync_token: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub items: Vec<Event>,
 }
@@ -94,13 +102,20 @@ pub struct Event {
 }
 
 impl Sendable for Event {
+    /// An empty action targets the event collection, e.g. to insert an event that carries its own
+    /// ID.
     fn path(&self, action: Option<String>) -> String {
-        progenitor_support::encode_path(&format!(
-            "calendars/{}/events/{}{}",
-            self.calendar_id,
-            self.id,
-            action.map_or_else(String::new, |x| format!("/{}", x))
-        ))
+        match action {
+            Some(action) if action.is_empty() => {
+                progenitor_support::encode_path(&format!("calendars/{}/events", self.calendar_id))
+            }
+            action => progenitor_support::encode_path(&format!(
+                "calendars/{}/events/{}{}",
+                self.calendar_id,
+                self.id,
+                action.map_or_else(String::new, |x| format!("/{}", x))
+            )),
+        }
     }
 
     fn query(&self) -> QueryParams {
@@ -116,6 +131,40 @@ impl Events {
     }
 }
 
+impl Page for Events {
+    type Item = Event;
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<Event> {
+        self.items
+    }
+}
+
+impl SyncPage for Events {
+    fn next_sync_token(&self) -> Option<&str> {
+        self.next_sync_token.as_deref()
+    }
+}
+
+impl SyncResult<Event> {
+    /// Events that were created or updated.
+    pub fn changed(&self) -> impl Iterator<Item = &Event> {
+        self.items
+            .iter()
+            .filter(|e| e.status != EventStatus::Cancelled)
+    }
+
+    /// Events that were deleted, they only carry their id.
+    pub fn deleted(&self) -> impl Iterator<Item = &Event> {
+        self.items
+            .iter()
+            .filter(|e| e.status == EventStatus::Cancelled)
+    }
+}
+
 impl Event {
     pub fn add_query(&mut self, key: String, value: String) {
         self.query_string.insert(key, value);
@@ -133,7 +182,7 @@ fn default_true() -> bool {
 }
 
 /// Taken from [google_calendar](https://github.com/oxidecomputer/third-party-api-clients/blob/720c61bf140726145503cdec3a4240c2843a6080/google/calendar/src/lib.rs#L184)
-mod progenitor_support {
+pub(crate) mod progenitor_support {
     use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 
     const PATH_SET: &AsciiSet = &CONTROLS
@@ -147,7 +196,6 @@ mod progenitor_support {
         .add(b'{')
         .add(b'}');
 
-    #[allow(dead_code)]
     pub(crate) fn encode_path(pc: &str) -> String {
         utf8_percent_encode(pc, PATH_SET).to_string()
     }
@@ -0,0 +1,243 @@
+use chrono::{DateTime, FixedOffset, TimeZone};
+use serde::Serialize;
+use url::Url;
+
+use super::{progenitor_support, types::EventType, ClientResult, Endpoints, QueryParams, Sendable};
+
+/* Google API Source: https://developers.google.com/calendar/api/v3/reference/events/list#parameters */
+
+/// EventOrderBy is the order of the events returned by a list.
+#[derive(Serialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EventOrderBy {
+    /// Ascending start time, only available together with `single_events`.
+    #[default]
+    StartTime,
+    /// Ascending last modification time.
+    Updated,
+}
+impl EventOrderBy {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Self::StartTime => "startTime",
+            Self::Updated => "updated",
+        }
+    }
+}
+impl std::fmt::Display for EventOrderBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
+/// EventListQuery holds the parameters of `events.list` and `events.instances`. Every parameter is
+/// optional, the server defaults apply to the ones left unset. `instances` only honours
+/// `max_attendees`, `max_results`, `show_deleted`, `time_min`, `time_max` and `time_zone`.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct EventListQuery {
+    pub q: Option<String>,
+    pub ical_uid: Option<String>,
+    pub max_results: Option<u16>,
+    pub max_attendees: Option<u32>,
+    pub order_by: Option<EventOrderBy>,
+    pub single_events: Option<bool>,
+    pub show_deleted: Option<bool>,
+    pub show_hidden_invitations: Option<bool>,
+    pub time_min: Option<DateTime<FixedOffset>>,
+    pub time_max: Option<DateTime<FixedOffset>>,
+    pub time_zone: Option<String>,
+    pub updated_min: Option<DateTime<FixedOffset>>,
+    pub event_types: Vec<EventType>,
+    /// `(name, value)` pairs matched against the private extended properties.
+    pub private_extended_properties: Vec<(String, String)>,
+    /// `(name, value)` pairs matched against the shared extended properties.
+    pub shared_extended_properties: Vec<(String, String)>,
+}
+
+impl EventListQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Free text search over the summary, description, location, attendees, etc.
+    pub fn q(mut self, q: impl ToString) -> Self {
+        self.q = Some(q.to_string());
+        self
+    }
+
+    pub fn ical_uid(mut self, ical_uid: impl ToString) -> Self {
+        self.ical_uid = Some(ical_uid.to_string());
+        self
+    }
+
+    /// Maximum number of events per page, the server caps it at 2500.
+    pub fn max_results(mut self, max_results: u16) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    pub fn max_attendees(mut self, max_attendees: u32) -> Self {
+        self.max_attendees = Some(max_attendees);
+        self
+    }
+
+    pub fn order_by(mut self, order_by: EventOrderBy) -> Self {
+        self.order_by = Some(order_by);
+        self
+    }
+
+    /// Expand recurring events into their instances.
+    pub fn single_events(mut self, single_events: bool) -> Self {
+        self.single_events = Some(single_events);
+        self
+    }
+
+    pub fn show_deleted(mut self, show_deleted: bool) -> Self {
+        self.show_deleted = Some(show_deleted);
+        self
+    }
+
+    pub fn show_hidden_invitations(mut self, show_hidden_invitations: bool) -> Self {
+        self.show_hidden_invitations = Some(show_hidden_invitations);
+        self
+    }
+
+    /// Only list events ending after this time.
+    pub fn time_min<Tz: TimeZone>(mut self, time_min: DateTime<Tz>) -> Self {
+        self.time_min = Some(time_min.fixed_offset());
+        self
+    }
+
+    /// Only list events starting before this time.
+    pub fn time_max<Tz: TimeZone>(mut self, time_max: DateTime<Tz>) -> Self {
+        self.time_max = Some(time_max.fixed_offset());
+        self
+    }
+
+    /// Time zone of the returned events, the calendar's by default.
+    pub fn time_zone(mut self, time_zone: impl ToString) -> Self {
+        self.time_zone = Some(time_zone.to_string());
+        self
+    }
+
+    /// Only list events modified after this time.
+    pub fn updated_min<Tz: TimeZone>(mut self, updated_min: DateTime<Tz>) -> Self {
+        self.updated_min = Some(updated_min.fixed_offset());
+        self
+    }
+
+    /// Only list events of this type, can be given several times.
+    pub fn event_type(mut self, event_type: EventType) -> Self {
+        self.event_types.push(event_type);
+        self
+    }
+
+    /// Only list events with this private extended property, can be given several times.
+    pub fn private_extended_property(mut self, name: impl ToString, value: impl ToString) -> Self {
+        self.private_extended_properties
+            .push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Only list events with this shared extended property, can be given several times.
+    pub fn shared_extended_property(mut self, name: impl ToString, value: impl ToString) -> Self {
+        self.shared_extended_properties
+            .push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// The query parameters, repeated parameters appear once per value.
+    pub(crate) fn params(&self) -> Vec<(String, String)> {
+        fn param(key: &str, value: impl ToString) -> (String, String) {
+            (key.to_string(), value.to_string())
+        }
+
+        let mut params = Vec::new();
+        params.extend(self.q.as_ref().map(|v| param("q", v)));
+        params.extend(self.ical_uid.as_ref().map(|v| param("iCalUID", v)));
+        params.extend(self.max_results.map(|v| param("maxResults", v)));
+        params.extend(self.max_attendees.map(|v| param("maxAttendees", v)));
+        params.extend(self.order_by.map(|v| param("orderBy", v)));
+        params.extend(self.single_events.map(|v| param("singleEvents", v)));
+        params.extend(self.show_deleted.map(|v| param("showDeleted", v)));
+        params.extend(
+            self.show_hidden_invitations
+                .map(|v| param("showHiddenInvitations", v)),
+        );
+        params.extend(self.time_min.map(|v| param("timeMin", v.to_rfc3339())));
+        params.extend(self.time_max.map(|v| param("timeMax", v.to_rfc3339())));
+        params.extend(self.time_zone.as_ref().map(|v| param("timeZone", v)));
+        params.extend(
+            self.updated_min
+                .map(|v| param("updatedMin", v.to_rfc3339())),
+        );
+        params.extend(self.event_types.iter().map(|v| param("eventTypes", v)));
+        params.extend(
+            self.private_extended_properties
+                .iter()
+                .map(|(k, v)| param("privateExtendedProperty", format!("{}={}", k, v))),
+        );
+        params.extend(
+            self.shared_extended_properties
+                .iter()
+                .map(|(k, v)| param("sharedExtendedProperty", format!("{}={}", k, v))),
+        );
+        params
+    }
+}
+
+/// EventListRequest is a GET on an event collection, it carries its parameters as a list so that
+/// repeated parameters survive.
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct EventListRequest {
+    #[serde(skip)]
+    path: String,
+    #[serde(skip)]
+    params: Vec<(String, String)>,
+}
+
+impl EventListRequest {
+    /// A request listing the events of a calendar.
+    pub(crate) fn list(calendar_id: &str, query: &EventListQuery) -> Self {
+        Self {
+            path: progenitor_support::encode_path(&format!("calendars/{}/events", calendar_id)),
+            params: query.params(),
+        }
+    }
+
+    /// A request listing the instances of a recurring event.
+    pub(crate) fn instances(calendar_id: &str, event_id: &str, query: &EventListQuery) -> Self {
+        Self {
+            path: progenitor_support::encode_path(&format!(
+                "calendars/{}/events/{}/instances",
+                calendar_id, event_id
+            )),
+            params: query.params(),
+        }
+    }
+
+    pub(crate) fn add_query(&mut self, key: String, value: String) {
+        self.params.push((key, value));
+    }
+}
+
+impl Sendable for EventListRequest {
+    fn path(&self, _action: Option<String>) -> String {
+        self.path.clone()
+    }
+
+    fn query(&self) -> QueryParams {
+        self.params.iter().cloned().collect()
+    }
+
+    fn url(&self, endpoints: &Endpoints, action: Option<String>) -> ClientResult<Url> {
+        Ok(Url::parse_with_params(
+            &format!("{}/{}", self.base_url(endpoints), self.path(action)),
+            &self.params,
+        )?)
+    }
+
+    fn body_bytes(&self) -> ClientResult<Vec<u8>> {
+        Ok(Vec::new())
+    }
+}
@@ -1,3 +1,4 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveTime};
 use serde::{Deserialize, Serialize};
 
 use super::{AdditionalProperties, DefaultReminder};
@@ -156,6 +157,21 @@ pub enum EventType {
     FocusTime,
     WorkingLocation,
 }
+impl EventType {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::OutOfOffice => "outOfOffice",
+            Self::FocusTime => "focusTime",
+            Self::WorkingLocation => "workingLocation",
+        }
+    }
+}
+impl std::fmt::Display for EventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
@@ -167,6 +183,20 @@ pub struct EventCalendarDate {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub time_zone: Option<String>,
 }
+impl EventCalendarDate {
+    /// The point in time this date refers to. All-day dates start at local midnight.
+    pub fn to_datetime(&self) -> Option<DateTime<FixedOffset>> {
+        if let Some(date_time) = &self.date_time {
+            return DateTime::parse_from_rfc3339(date_time).ok();
+        }
+        NaiveDate::parse_from_str(self.date.as_ref()?, "%Y-%m-%d")
+            .ok()?
+            .and_time(NaiveTime::MIN)
+            .and_local_timezone(Local)
+            .earliest()
+            .map(|d| d.fixed_offset())
+    }
+}
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
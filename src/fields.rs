@@ -0,0 +1,76 @@
+use serde::{de::DeserializeOwned, Deserialize};
+
+use super::{
+    types::{EventCalendarDate, EventStatus},
+    CalendarAccessRole, Page,
+};
+
+/* Google API Source: https://developers.google.com/calendar/api/guides/performance#partial */
+
+/// Projection is a lightweight type deserialized from a partial response. `FIELDS` is the field
+/// mask of a single resource, the clients wrap it as needed (e.g. `items(...)` for lists).
+pub trait Projection: DeserializeOwned {
+    const FIELDS: &'static str;
+}
+
+/// EventSummary is the bare minimum needed to place an event on a calendar view.
+#[derive(Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct EventSummary {
+    pub id: String,
+    pub summary: String,
+    pub start: EventCalendarDate,
+    pub end: EventCalendarDate,
+    pub status: EventStatus,
+}
+
+impl Projection for EventSummary {
+    const FIELDS: &'static str = "id,summary,start,end,status";
+}
+
+/// CalendarListSummary is the bare minimum needed to list the calendars of a user.
+#[derive(Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CalendarListSummary {
+    pub id: String,
+    pub summary: String,
+    pub access_role: CalendarAccessRole,
+    pub primary: Option<bool>,
+}
+
+impl Projection for CalendarListSummary {
+    const FIELDS: &'static str = "id,summary,accessRole,primary";
+}
+
+/// Items is the list envelope used to deserialize projections out of a list response.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Items<T> {
+    #[serde(default = "Vec::new")]
+    pub items: Vec<T>,
+    #[serde(default)]
+    pub next_page_token: Option<String>,
+}
+
+impl<T: DeserializeOwned> Page for Items<T> {
+    type Item = T;
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<T> {
+        self.items
+    }
+}
+
+/// The mask selecting `fields` out of each item of a list response, along with the page token.
+pub(crate) fn items_mask(fields: &str) -> String {
+    format!("nextPageToken,items({})", fields)
+}
+
+/// The mask selecting `fields` out of each item of a list response, along with the tokens that
+/// pagination and incremental sync rely on.
+pub(crate) fn list_mask(fields: &str) -> String {
+    format!("nextPageToken,nextSyncToken,items({})", fields)
+}
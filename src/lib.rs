@@ -32,6 +32,18 @@ pub use oauth::*;
 mod calendar;
 pub use calendar::*;
 
+/// Access control lists, sharing calendars with users, groups and domains.
+mod acl;
+pub use acl::*;
+
+/// Color palettes, resolving `color_id`s into RGB colors.
+mod colors;
+pub use colors::{ColorDefinition, Colors, ColorsClient};
+
+/// User settings, e.g. time zone and week start.
+mod settings;
+pub use settings::{Setting, Settings, SettingsClient, UserSettings};
+
 /// Events, the method you will work with most; events in a single calendar.
 mod event;
 pub use event::*;
@@ -44,5 +56,49 @@ pub use user::*;
 mod sendable;
 pub use sendable::*;
 
+/// Partial responses, requesting only some fields of a resource.
+mod fields;
+pub use fields::{CalendarListSummary, EventSummary, Projection};
+
+/// Lazy pagination of list responses.
+mod pagination;
+pub use pagination::Page;
+
+/// Incremental synchronization through sync tokens.
+mod sync;
+pub use sync::{SyncPage, SyncResult};
+
+/// Push notification channels, watching resources for changes.
+mod channel;
+pub use channel::{Channel, ChannelClient};
+
+/// Embedded HTTP receiver for push notifications.
+#[cfg(feature = "webhook")]
+mod webhook;
+#[cfg(feature = "webhook")]
+pub use webhook::{Notification, ResourceState, WebhookReceiver};
+
+/// Local, persistent mirror of calendars kept current through incremental sync.
+mod mirror;
+pub use mirror::{Mirror, MirrorSync, MirroredCalendar};
+
+/// Offline queue of event mutations, replayed with conflict detection.
+mod queue;
+pub use queue::{Conflict, ConflictStrategy, Mutation, MutationQueue, ReplayReport, Resolution};
+
+/// Batch requests, sending many operations at once.
+mod batch;
+pub use batch::*;
+
+/// Retry policy applied by the client to transient failures.
+mod retry;
+pub use retry::{RetryEvent, RetryPolicy};
+
+/// Client side rate limiting.
+mod rate_limit;
+pub use rate_limit::{BucketState, Quota, RateLimiter};
+
+mod telemetry;
+
 mod error;
-pub use error::{ClientError, ClientResult};
+pub use error::{ApiError, ApiErrorDetail, ClientError, ClientResult};
@@ -0,0 +1,245 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, TimeZone};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{
+    CalendarListClient, CalendarListItem, ClientError, ClientResult, Event, EventClient,
+    EventListQuery,
+};
+
+const CALENDARS_DIR: &str = "calendars";
+const CALENDAR_LIST_FILE: &str = "calendar_list.json";
+
+/// MirroredCalendar is the local copy of a single calendar: its calendar list entry, its events
+/// and the token to sync them from.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MirroredCalendar {
+    pub calendar: Option<CalendarListItem>,
+    pub sync_token: Option<String>,
+    /// Events by id.
+    pub events: BTreeMap<String, Event>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+struct CalendarListState {
+    sync_token: Option<String>,
+}
+
+/// MirrorSync reports what a `Mirror::sync` did.
+#[derive(Debug, Default)]
+pub struct MirrorSync {
+    /// Calendars whose events were synced.
+    pub synced: Vec<String>,
+    /// Calendars that had to be fully resynced, because they were new or their token expired.
+    pub full_resyncs: Vec<String>,
+    /// Calendars that could not be synced, they keep their previous state.
+    pub failures: Vec<(String, ClientError)>,
+    /// Set when the calendar list could not be synced, calendar metadata is then left as is.
+    pub calendar_list_error: Option<ClientError>,
+}
+
+/// Mirror is a local, persistent copy of selected calendars kept current through incremental
+/// sync. Reads never hit the network, so the mirror keeps working offline and survives restarts.
+/// Recurring events are mirrored as their individual instances, so that time range queries see
+/// every occurrence.
+///
+/// Each calendar is stored as a JSON file under `{dir}/calendars`.
+#[derive(Debug)]
+pub struct Mirror {
+    dir: PathBuf,
+    calendars: BTreeMap<String, MirroredCalendar>,
+    calendar_list: CalendarListState,
+}
+
+impl Mirror {
+    /// Open the mirror stored in `dir`, creating it if needed.
+    pub async fn open(dir: impl Into<PathBuf>) -> ClientResult<Self> {
+        let dir = dir.into();
+        tokio::fs::create_dir_all(dir.join(CALENDARS_DIR)).await?;
+
+        let mut calendars = BTreeMap::new();
+        let mut entries = tokio::fs::read_dir(dir.join(CALENDARS_DIR)).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let Some(calendar_id) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_suffix(".json"))
+                .map(|n| percent_decode_str(n).decode_utf8_lossy().into_owned())
+            else {
+                continue;
+            };
+            let mut calendar: MirroredCalendar = read(&path).await?;
+            calendar
+                .events
+                .values_mut()
+                .for_each(|e| e.calendar_id = calendar_id.clone());
+            calendars.insert(calendar_id, calendar);
+        }
+
+        let calendar_list_path = dir.join(CALENDAR_LIST_FILE);
+        let calendar_list = if tokio::fs::try_exists(&calendar_list_path).await? {
+            read(&calendar_list_path).await?
+        } else {
+            CalendarListState::default()
+        };
+
+        Ok(Self {
+            dir,
+            calendars,
+            calendar_list,
+        })
+    }
+
+    /// Start mirroring a calendar, its events are fetched on the next sync.
+    pub async fn track(&mut self, calendar_id: impl ToString) -> ClientResult<()> {
+        let calendar_id = calendar_id.to_string();
+        if self.calendars.contains_key(&calendar_id) {
+            return Ok(());
+        }
+        self.calendars
+            .insert(calendar_id.clone(), MirroredCalendar::default());
+        self.save_calendar(&calendar_id).await?;
+
+        // The metadata of the new calendar only comes with a full calendar list sync.
+        self.calendar_list.sync_token = None;
+        self.save_calendar_list().await
+    }
+
+    /// Stop mirroring a calendar and forget its local copy.
+    pub async fn untrack(&mut self, calendar_id: &str) -> ClientResult<()> {
+        if self.calendars.remove(calendar_id).is_some() {
+            tokio::fs::remove_file(self.calendar_path(calendar_id)).await?;
+        }
+        Ok(())
+    }
+
+    /// Every mirrored calendar, by id.
+    pub fn calendars(&self) -> &BTreeMap<String, MirroredCalendar> {
+        &self.calendars
+    }
+
+    pub fn calendar(&self, calendar_id: &str) -> Option<&MirroredCalendar> {
+        self.calendars.get(calendar_id)
+    }
+
+    /// The mirrored events of every calendar overlapping the start and end times, by start time.
+    pub fn events_between<Tz: TimeZone>(
+        &self,
+        start_time: DateTime<Tz>,
+        end_time: DateTime<Tz>,
+    ) -> Vec<&Event> {
+        let (start_time, end_time) = (start_time.fixed_offset(), end_time.fixed_offset());
+        let mut events: Vec<_> = self
+            .calendars
+            .values()
+            .flat_map(|c| c.events.values())
+            .filter_map(|e| Some((e.start.to_datetime()?, e.end.to_datetime()?, e)))
+            .filter(|(start, end, _)| *start < end_time && *end > start_time)
+            .collect();
+        events.sort_by_key(|(start, _, _)| *start);
+        events.into_iter().map(|(_, _, e)| e).collect()
+    }
+
+    /// Bring every mirrored calendar up to date. Calendars that fail to sync are reported and keep
+    /// their previous state, only local storage errors abort the sync. The field masks of the
+    /// clients are ignored, the mirror keeps whole resources and relies on their status.
+    pub async fn sync(
+        &mut self,
+        events: &EventClient,
+        calendars: &CalendarListClient,
+    ) -> ClientResult<MirrorSync> {
+        let (events, calendars) = (events.unmasked(), calendars.unmasked());
+        let mut report = MirrorSync::default();
+
+        match calendars.sync(self.calendar_list.sync_token.clone()).await {
+            Ok(result) => {
+                for item in &result.items {
+                    if let Some(calendar) = self.calendars.get_mut(&item.id) {
+                        calendar.calendar = (item.deleted != Some(true)).then(|| item.clone());
+                    }
+                }
+                self.calendar_list.sync_token = Some(result.next_sync_token);
+                self.save_calendar_list().await?;
+            }
+            Err(error) => report.calendar_list_error = Some(error),
+        }
+
+        let calendar_ids: Vec<String> = self.calendars.keys().cloned().collect();
+        for calendar_id in calendar_ids {
+            let calendar = self
+                .calendars
+                .get_mut(&calendar_id)
+                .expect("Calendar is tracked");
+            let result = match events
+                .sync_query(
+                    calendar_id.clone(),
+                    EventListQuery::new().single_events(true),
+                    calendar.sync_token.clone(),
+                )
+                .await
+            {
+                Ok(result) => result,
+                Err(error) => {
+                    report.failures.push((calendar_id, error));
+                    continue;
+                }
+            };
+
+            if result.full_resync {
+                calendar.events.clear();
+                report.full_resyncs.push(calendar_id.clone());
+            }
+            for event in result.items {
+                if event.status == super::types::EventStatus::Cancelled {
+                    calendar.events.remove(&event.id);
+                } else {
+                    calendar.events.insert(event.id.clone(), event);
+                }
+            }
+            calendar.sync_token = Some(result.next_sync_token);
+
+            self.save_calendar(&calendar_id).await?;
+            report.synced.push(calendar_id);
+        }
+        Ok(report)
+    }
+
+    fn calendar_path(&self, calendar_id: &str) -> PathBuf {
+        self.dir.join(CALENDARS_DIR).join(format!(
+            "{}.json",
+            utf8_percent_encode(calendar_id, NON_ALPHANUMERIC)
+        ))
+    }
+
+    async fn save_calendar(&self, calendar_id: &str) -> ClientResult<()> {
+        write(
+            &self.calendar_path(calendar_id),
+            &self.calendars[calendar_id],
+        )
+        .await
+    }
+
+    async fn save_calendar_list(&self) -> ClientResult<()> {
+        write(&self.dir.join(CALENDAR_LIST_FILE), &self.calendar_list).await
+    }
+}
+
+pub(crate) async fn read<T: DeserializeOwned>(path: &Path) -> ClientResult<T> {
+    Ok(serde_json::from_slice(&tokio::fs::read(path).await?)?)
+}
+
+/// Write the file through a temporary one, so that a crash never leaves it half written.
+pub(crate) async fn write<T: Serialize>(path: &Path, value: &T) -> ClientResult<()> {
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, serde_json::to_vec(value)?).await?;
+    tokio::fs::rename(&tmp, path).await?;
+    Ok(())
+}
@@ -29,6 +29,25 @@ pub struct OAuthRequest {
     pub scope: String,
 }
 
+/// OAuthEndpoints are the authorization, token and revocation URLs used during the OAuth2 flow.
+/// The defaults point at Google.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OAuthEndpoints {
+    pub auth: String,
+    pub token: String,
+    pub revocation: String,
+}
+
+impl Default for OAuthEndpoints {
+    fn default() -> Self {
+        Self {
+            auth: "https://accounts.google.com/o/oauth2/v2/auth".to_string(),
+            token: "https://www.googleapis.com/oauth2/v3/token".to_string(),
+            revocation: "https://oauth2.googleapis.com/revoke".to_string(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct OAuth {
     client: BasicClient,
@@ -41,24 +60,34 @@ impl OAuth {
         client_secret: impl ToString,
         redir_url: impl ToString,
     ) -> Self {
-        // Set up the config for the Google OAuth2 process.
+        Self::with_endpoints(
+            client_id,
+            client_secret,
+            redir_url,
+            OAuthEndpoints::default(),
+        )
+    }
+
+    /// Same as `new`, but negotiates against the given endpoints instead of Google's.
+    pub fn with_endpoints(
+        client_id: impl ToString,
+        client_secret: impl ToString,
+        redir_url: impl ToString,
+        endpoints: OAuthEndpoints,
+    ) -> Self {
+        // Set up the config for the OAuth2 process.
         Self {
             client: BasicClient::new(
                 ClientId::new(client_id.to_string()),
                 Some(ClientSecret::new(client_secret.to_string())),
-                AuthUrl::new("https://accounts.google.com/o/oauth2/v2/auth".to_string())
-                    .expect("Invalid authorization endpoint URL"),
-                Some(
-                    TokenUrl::new("https://www.googleapis.com/oauth2/v3/token".to_string())
-                        .expect("Invalid token endpoint URL"),
-                ),
+                AuthUrl::new(endpoints.auth).expect("Invalid authorization endpoint URL"),
+                Some(TokenUrl::new(endpoints.token).expect("Invalid token endpoint URL")),
             )
             .set_redirect_uri(
                 RedirectUrl::new(redir_url.to_string()).expect("Invalid redirect URL"),
             )
             .set_revocation_uri(
-                RevocationUrl::new("https://oauth2.googleapis.com/revoke".to_string())
-                    .expect("Invalid revocation endpoint URL"),
+                RevocationUrl::new(endpoints.revocation).expect("Invalid revocation endpoint URL"),
             ),
             pkce_code_verifier: Mutex::new(None),
         }
@@ -124,11 +153,26 @@ impl OAuth {
 
     pub async fn refresh(&self, token: &mut OToken) -> Result<()> {
         if token.is_expired() {
-            let t = self
-                .exhange_refresh(token.refresh.take().context("Refresh token should exist")?)
-                .await?;
-            token.take_over(t);
+            self.force_refresh(token).await?;
+        }
+        Ok(())
+    }
+
+    /// Refresh the token even though it has not expired yet, e.g. because the server rejected it.
+    pub async fn force_refresh(&self, token: &mut OToken) -> Result<()> {
+        let mut t = self
+            .exhange_refresh(
+                token
+                    .refresh
+                    .clone()
+                    .context("Refresh token should exist")?,
+            )
+            .await?;
+        // Google does not always hand out a new refresh token, keep the old one in that case.
+        if t.refresh.is_none() {
+            t.refresh = token.refresh.take();
         }
+        token.take_over(t);
         Ok(())
     }
 
@@ -173,7 +217,7 @@ impl OAuth {
                         .ok()?;
 
                     // The server will terminate itself after collecting the first code.
-                    break (Some(auth));
+                    break Some(auth);
                 }
             }
         }
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use futures::{stream, Stream, StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+use tracing::Instrument;
+
+use super::{ClientResult, GCalClient, Sendable};
+
+/// Page is a single page of a list response, followed by the next one until `next_page_token` is
+/// exhausted.
+pub trait Page: DeserializeOwned {
+    type Item;
+
+    fn next_page_token(&self) -> Option<&str>;
+
+    fn into_items(self) -> Vec<Self::Item>;
+}
+
+/// Lazily fetch every page of a list. `target` builds the request of a page from its page token,
+/// `None` being the first page.
+pub(crate) fn pages<S, P>(
+    client: Arc<GCalClient>,
+    mut target: impl FnMut(Option<String>) -> S,
+) -> impl Stream<Item = ClientResult<P>>
+where
+    S: Sendable,
+    P: Page,
+{
+    let mut page_number = 0u32;
+    stream::try_unfold(Some(None), move |token: Option<Option<String>>| {
+        let client = client.clone();
+        let request = token.map(&mut target);
+        page_number += 1;
+        let span = tracing::debug_span!("gcal_page", page = page_number);
+
+        async move {
+            let Some(request) = request else {
+                return Ok(None);
+            };
+            let page: P = client
+                .get(None, request)
+                .instrument(span.clone())
+                .await?
+                .json()
+                .instrument(span)
+                .await?;
+            let next = page.next_page_token().map(|t| Some(t.to_string()));
+            Ok(Some((page, next)))
+        }
+    })
+}
+
+/// Flatten a stream of pages into a stream of their items.
+pub(crate) fn items<P: Page>(
+    pages: impl Stream<Item = ClientResult<P>>,
+) -> impl Stream<Item = ClientResult<P::Item>> {
+    pages
+        .map_ok(|page| stream::iter(page.into_items().into_iter().map(Ok)))
+        .try_flatten()
+}
+
+/// Collect at most `max_items` items, no further page is fetched once the cap is reached.
+pub(crate) async fn collect_items<T>(
+    items: impl Stream<Item = ClientResult<T>>,
+    max_items: Option<usize>,
+) -> ClientResult<Vec<T>> {
+    match max_items {
+        Some(max) => items.take(max).try_collect().await,
+        None => items.try_collect().await,
+    }
+}
@@ -0,0 +1,349 @@
+use std::{
+    collections::{hash_map::RandomState, VecDeque},
+    hash::BuildHasher,
+    path::PathBuf,
+    sync::Arc,
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    mirror::{read, write},
+    ClientError, ClientResult, Event, EventClient,
+};
+
+type MergeHook = Arc<dyn Fn(&Mutation, &Event) -> Resolution + Send + Sync>;
+
+/// Mutation is a change to an event recorded while offline. Updates and deletes carry the event as
+/// it was last seen, its `etag` and `sequence` tell whether the server copy changed since.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Mutation {
+    #[serde(rename_all = "camelCase")]
+    Insert { calendar_id: String, event: Event },
+    #[serde(rename_all = "camelCase")]
+    Update { calendar_id: String, event: Event },
+    #[serde(rename_all = "camelCase")]
+    Delete { calendar_id: String, event: Event },
+}
+
+impl Mutation {
+    pub fn calendar_id(&self) -> &str {
+        match self {
+            Self::Insert { calendar_id, .. }
+            | Self::Update { calendar_id, .. }
+            | Self::Delete { calendar_id, .. } => calendar_id,
+        }
+    }
+
+    pub fn event(&self) -> &Event {
+        match self {
+            Self::Insert { event, .. }
+            | Self::Update { event, .. }
+            | Self::Delete { event, .. } => event,
+        }
+    }
+
+    /// The event with its calendar restored, ready to be sent.
+    fn to_event(&self) -> Event {
+        let mut event = self.event().clone();
+        event.calendar_id = self.calendar_id().to_string();
+        event
+    }
+}
+
+/// Resolution is how a conflict between a queued mutation and the server copy was settled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    /// Keep the server copy and drop the mutation.
+    Discard,
+    /// Overwrite the server copy with this event.
+    Apply(Box<Event>),
+    /// Delete the event on the server.
+    Delete,
+}
+
+/// ConflictStrategy decides what happens when the server copy of an event changed after a
+/// mutation was queued.
+#[derive(Clone, Default)]
+pub enum ConflictStrategy {
+    /// The server copy is kept.
+    #[default]
+    ServerWins,
+    /// The mutation is applied over the server copy.
+    ClientWins,
+    /// The callback receives the mutation and the server copy and picks the resolution.
+    Merge(MergeHook),
+}
+
+impl ConflictStrategy {
+    pub fn merge(hook: impl Fn(&Mutation, &Event) -> Resolution + Send + Sync + 'static) -> Self {
+        Self::Merge(Arc::new(hook))
+    }
+
+    fn resolve(&self, mutation: &Mutation, server: &Event) -> Resolution {
+        match (self, mutation) {
+            (Self::ServerWins, _) | (Self::ClientWins, Mutation::Insert { .. }) => {
+                Resolution::Discard
+            }
+            (Self::ClientWins, Mutation::Update { event, .. }) => {
+                Resolution::Apply(Box::new(event.clone()))
+            }
+            (Self::ClientWins, Mutation::Delete { .. }) => Resolution::Delete,
+            (Self::Merge(hook), _) => hook(mutation, server),
+        }
+    }
+}
+
+impl std::fmt::Debug for ConflictStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ServerWins => write!(f, "ServerWins"),
+            Self::ClientWins => write!(f, "ClientWins"),
+            Self::Merge(_) => write!(f, "Merge"),
+        }
+    }
+}
+
+/// Conflict is a mutation that met a changed server copy during replay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub mutation: Mutation,
+    /// The server copy the mutation conflicted with.
+    pub server: Event,
+    pub resolution: Resolution,
+}
+
+/// ReplayReport reports what a `MutationQueue::replay` did.
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    /// Mutations applied without conflict, along with the server copy they produced. There is no
+    /// copy for deletions, nor for insertions found already applied by an earlier replay.
+    pub applied: Vec<(Mutation, Option<Event>)>,
+    pub conflicts: Vec<Conflict>,
+    /// Mutations the server refused with a client error, they are dropped from the queue.
+    pub failed: Vec<(Mutation, ClientError)>,
+    /// Set when the replay stopped early on any other error, e.g. the server or the token endpoint
+    /// could not be reached. The remaining mutations stay queued for the next replay.
+    pub stopped: Option<ClientError>,
+}
+
+enum Replayed {
+    Applied(Option<Event>),
+    Conflict(Event, Resolution),
+}
+
+/// MutationQueue records event mutations while offline and replays them in order once the server
+/// can be reached again. The queue is persisted to a JSON file after every change.
+#[derive(Debug)]
+pub struct MutationQueue {
+    path: PathBuf,
+    mutations: VecDeque<Mutation>,
+}
+
+impl MutationQueue {
+    /// Open the queue stored at `path`, starting empty if the file does not exist.
+    pub async fn open(path: impl Into<PathBuf>) -> ClientResult<Self> {
+        let path = path.into();
+        let mutations = if tokio::fs::try_exists(&path).await? {
+            read(&path).await?
+        } else {
+            VecDeque::new()
+        };
+        Ok(Self { path, mutations })
+    }
+
+    /// Queue the insertion of an event. Events without an ID get a random one, so that replaying
+    /// the insertion never creates a duplicate and later mutations can refer to the event. The
+    /// queued event is returned.
+    pub async fn insert(&mut self, mut event: Event) -> ClientResult<Event> {
+        if event.id.is_empty() {
+            event.id = event_id();
+        }
+        self.push(Mutation::Insert {
+            calendar_id: event.calendar_id.clone(),
+            event: event.clone(),
+        })
+        .await?;
+        Ok(event)
+    }
+
+    /// Queue the update of an event, which must already exist on the server.
+    pub async fn update(&mut self, event: Event) -> ClientResult<()> {
+        Self::check_id(&event)?;
+        self.push(Mutation::Update {
+            calendar_id: event.calendar_id.clone(),
+            event,
+        })
+        .await
+    }
+
+    /// Queue the deletion of an event, which must already exist on the server.
+    pub async fn delete(&mut self, event: Event) -> ClientResult<()> {
+        Self::check_id(&event)?;
+        self.push(Mutation::Delete {
+            calendar_id: event.calendar_id.clone(),
+            event,
+        })
+        .await
+    }
+
+    pub async fn push(&mut self, mutation: Mutation) -> ClientResult<()> {
+        self.mutations.push_back(mutation);
+        self.save().await
+    }
+
+    /// The queued mutations, oldest first.
+    pub fn mutations(&self) -> &VecDeque<Mutation> {
+        &self.mutations
+    }
+
+    pub fn len(&self) -> usize {
+        self.mutations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mutations.is_empty()
+    }
+
+    /// Replay the queued mutations in order. Each mutation leaves the queue once the server
+    /// answered it, the replay stops at the first network, rate limit or server error so that the
+    /// order is kept.
+    pub async fn replay(
+        &mut self,
+        client: &EventClient,
+        strategy: &ConflictStrategy,
+    ) -> ClientResult<ReplayReport> {
+        let mut report = ReplayReport::default();
+        while let Some(mutation) = self.mutations.front().cloned() {
+            match replay(client, &mutation, strategy).await {
+                Ok(Replayed::Applied(event)) => report.applied.push((mutation, event)),
+                Ok(Replayed::Conflict(server, resolution)) => report.conflicts.push(Conflict {
+                    mutation,
+                    server,
+                    resolution,
+                }),
+                // Only an answer of the server settles the mutation, anything else (e.g. the
+                // token could not be refreshed while offline) leaves it queued.
+                Err(error) if error.api_error().is_some() && !error.is_transient() => {
+                    report.failed.push((mutation, error))
+                }
+                Err(error) => {
+                    report.stopped = Some(error);
+                    break;
+                }
+            }
+            self.mutations.pop_front();
+            self.save().await?;
+        }
+        Ok(report)
+    }
+
+    fn check_id(event: &Event) -> ClientResult<()> {
+        if event.id.is_empty() {
+            return Err(ClientError::UnknownError(
+                "Only events known to the server can be updated or deleted".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn save(&self) -> ClientResult<()> {
+        write(&self.path, &self.mutations).await
+    }
+}
+
+/// Send a single mutation. Without an etag the server copy is fetched first, and counts as changed
+/// when its sequence is ahead of the queued one.
+async fn replay(
+    client: &EventClient,
+    mutation: &Mutation,
+    strategy: &ConflictStrategy,
+) -> ClientResult<Replayed> {
+    let mut event = mutation.to_event();
+    let is_delete = matches!(mutation, Mutation::Delete { .. });
+    if let Mutation::Insert { .. } = mutation {
+        // A 409 means the event already exists: an earlier replay got through without an answer.
+        return match client.insert(event).await {
+            Ok(event) => Ok(Replayed::Applied(Some(event))),
+            Err(error) if error.status() == Some(409) => Ok(Replayed::Applied(None)),
+            Err(error) => Err(error),
+        };
+    }
+
+    if event.etag.is_empty() {
+        let server = match server_copy(client, &event).await {
+            Err(ClientError::NotFound(_) | ClientError::Gone(_)) if is_delete => {
+                return Ok(Replayed::Applied(None))
+            }
+            server => server?,
+        };
+        if server.sequence > event.sequence {
+            return resolve(client, mutation, server, strategy).await;
+        }
+        event.etag = server.etag;
+    }
+
+    let result = if is_delete {
+        client.delete_if_match(event.clone()).await.map(|_| None)
+    } else {
+        client.update_if_match(event.clone()).await.map(Some)
+    };
+    match result {
+        Ok(event) => Ok(Replayed::Applied(event)),
+        Err(ClientError::NotFound(_) | ClientError::Gone(_)) if is_delete => {
+            Ok(Replayed::Applied(None))
+        }
+        Err(ClientError::PreconditionFailed(_)) => {
+            let server = server_copy(client, &event).await?;
+            resolve(client, mutation, server, strategy).await
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Settle a conflict with the strategy, the resolution is applied conditionally on the server copy
+/// it was based on.
+async fn resolve(
+    client: &EventClient,
+    mutation: &Mutation,
+    server: Event,
+    strategy: &ConflictStrategy,
+) -> ClientResult<Replayed> {
+    let resolution = strategy.resolve(mutation, &server);
+    match &resolution {
+        Resolution::Discard => {}
+        Resolution::Apply(event) => {
+            let mut event = event.as_ref().clone();
+            event.calendar_id = server.calendar_id.clone();
+            event.id = server.id.clone();
+            event.etag = server.etag.clone();
+            client.update_if_match(event).await?;
+        }
+        Resolution::Delete => client.delete_if_match(server.clone()).await?,
+    }
+    Ok(Replayed::Conflict(server, resolution))
+}
+
+async fn server_copy(client: &EventClient, event: &Event) -> ClientResult<Event> {
+    let mut server = client
+        .get(event.calendar_id.clone(), event.id.clone())
+        .await?;
+    server.calendar_id = event.calendar_id.clone();
+    Ok(server)
+}
+
+/// A random event ID. Google requires 5 to 1024 base32hex characters, this uses 26 of them.
+fn event_id() -> String {
+    const BASE32HEX: &[u8; 32] = b"0123456789abcdefghijklmnopqrstuv";
+    let mut id = String::with_capacity(26);
+    for half in 0..2u8 {
+        let mut bits = RandomState::new().hash_one((half, SystemTime::now()));
+        for _ in 0..13 {
+            id.push(BASE32HEX[(bits & 31) as usize] as char);
+            bits >>= 5;
+        }
+    }
+    id
+}
@@ -0,0 +1,208 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Slowest rate a quota may have, one request every 1000 seconds.
+const MIN_PER_SECOND: f64 = 1e-3;
+/// Number of `quotaUser` buckets past which idle ones are dropped.
+const USERS_PRUNED_AT: usize = 1024;
+
+/// Quota is the sustained rate of a token bucket and how many requests it lets through in a burst.
+/// Rates below `MIN_PER_SECOND`, zero and negative ones included, are raised to it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quota {
+    pub per_second: f64,
+    pub burst: u32,
+}
+
+impl Quota {
+    /// A quota of `per_second` requests, bursting up to one second worth of requests.
+    pub fn per_second(per_second: f64) -> Self {
+        let per_second = per_second.max(MIN_PER_SECOND);
+        Self {
+            per_second,
+            burst: per_second.ceil().max(1.) as u32,
+        }
+    }
+
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+}
+
+/// BucketState is a snapshot of a token bucket, meant for metrics. `available` goes negative when
+/// requests are queued up waiting for tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BucketState {
+    pub available: f64,
+    pub capacity: u32,
+    pub per_second: f64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    quota: Quota,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    fn new(mut quota: Quota) -> Self {
+        // The fields of a quota are public, so it may not have gone through `Quota::per_second`.
+        quota.per_second = quota.per_second.max(MIN_PER_SECOND);
+        quota.burst = quota.burst.max(1);
+        Self {
+            quota,
+            tokens: quota.burst as f64,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.quota.per_second).min(self.quota.burst as f64);
+        self.refilled_at = now;
+    }
+
+    /// Take `tokens` tokens, returning how long the caller has to wait before it may use them.
+    fn reserve(&mut self, tokens: u32) -> Duration {
+        self.refill();
+        self.tokens -= tokens as f64;
+        if self.tokens >= 0. {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.quota.per_second)
+        }
+    }
+
+    /// Whether the bucket is full again, i.e. no different from a new one.
+    fn is_idle(&mut self) -> bool {
+        self.refill();
+        self.tokens >= self.quota.burst as f64
+    }
+
+    fn state(&mut self) -> BucketState {
+        self.refill();
+        BucketState {
+            available: self.tokens,
+            capacity: self.quota.burst,
+            per_second: self.quota.per_second,
+        }
+    }
+}
+
+/// RateLimiter is a token bucket governor that smooths out bursts of requests instead of letting
+/// Google reject them. It holds a global bucket and one bucket per `quotaUser`, requests without a
+/// `quotaUser` share a single bucket. Buckets of users that went idle are dropped as new users come
+/// in, so the state of a user may disappear from `user_states` once its bucket is full again.
+///
+/// Wrap it in an `Arc` to share it between several clients; cloned clients always share it.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    global: Option<Mutex<Bucket>>,
+    per_user: Option<Quota>,
+    users: Mutex<Users>,
+}
+
+/// The `quotaUser` buckets, pruned whenever their number reaches `prune_at`.
+#[derive(Debug)]
+struct Users {
+    buckets: HashMap<String, Bucket>,
+    prune_at: usize,
+}
+
+impl Default for Users {
+    fn default() -> Self {
+        Self {
+            buckets: HashMap::new(),
+            prune_at: USERS_PRUNED_AT,
+        }
+    }
+}
+
+impl Users {
+    fn bucket(&mut self, quota_user: &str, quota: Quota) -> &mut Bucket {
+        if !self.buckets.contains_key(quota_user) && self.buckets.len() >= self.prune_at {
+            self.buckets.retain(|_, bucket| !bucket.is_idle());
+            // Users that are still busy stay, prune again once as many new ones came in.
+            self.prune_at = USERS_PRUNED_AT.max(self.buckets.len() * 2);
+        }
+        self.buckets
+            .entry(quota_user.to_string())
+            .or_insert_with(|| Bucket::new(quota))
+    }
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit all requests going through this limiter.
+    pub fn global(mut self, quota: Quota) -> Self {
+        self.global = Some(Mutex::new(Bucket::new(quota)));
+        self
+    }
+
+    /// Limit the requests of every `quotaUser` separately.
+    pub fn per_user(mut self, quota: Quota) -> Self {
+        self.per_user = Some(quota);
+        self
+    }
+
+    /// Wait until a request for the given `quotaUser` may be sent.
+    pub async fn acquire(&self, quota_user: Option<&str>) {
+        self.acquire_many(quota_user, 1).await
+    }
+
+    /// Wait until `requests` requests for the given `quotaUser` may be sent, e.g. the operations
+    /// of a batch that Google counts one by one.
+    pub async fn acquire_many(&self, quota_user: Option<&str>, requests: u32) {
+        let mut wait = Duration::ZERO;
+        if let Some(quota) = self.per_user {
+            let mut users = self.users.lock().expect("Rate limiter lock poisoned");
+            wait = users
+                .bucket(quota_user.unwrap_or_default(), quota)
+                .reserve(requests);
+        }
+        if let Some(global) = &self.global {
+            let mut global = global.lock().expect("Rate limiter lock poisoned");
+            wait = wait.max(global.reserve(requests));
+        }
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// State of the global bucket, if there is a global limit.
+    pub fn global_state(&self) -> Option<BucketState> {
+        self.global
+            .as_ref()
+            .map(|g| g.lock().expect("Rate limiter lock poisoned").state())
+    }
+
+    /// State of the bucket of a `quotaUser`, if it sent a request recently.
+    pub fn user_state(&self, quota_user: &str) -> Option<BucketState> {
+        self.users
+            .lock()
+            .expect("Rate limiter lock poisoned")
+            .buckets
+            .get_mut(quota_user)
+            .map(Bucket::state)
+    }
+
+    /// State of every `quotaUser` bucket.
+    pub fn user_states(&self) -> Vec<(String, BucketState)> {
+        self.users
+            .lock()
+            .expect("Rate limiter lock poisoned")
+            .buckets
+            .iter_mut()
+            .map(|(user, bucket)| (user.clone(), bucket.state()))
+            .collect()
+    }
+}
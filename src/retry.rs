@@ -0,0 +1,246 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use reqwest::Method;
+use url::Url;
+
+use super::ClientError;
+
+type RetryHook = Arc<dyn Fn(&RetryEvent) + Send + Sync>;
+
+/// RetryPolicy decides whether a failed request is sent again and how long to wait before doing
+/// so. Rate limits, server errors and connection failures are retried with an exponential backoff.
+///
+/// Only idempotent verbs (GET, PUT, DELETE, ...) are retried by default, POST and PATCH requests
+/// must be opted in with `retry_mutations`.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    retry_mutations: bool,
+    on_retry: Option<RetryHook>,
+}
+
+/// RetryEvent describes a retry that is about to happen, it is handed to the `on_retry` hook.
+#[derive(Debug)]
+pub struct RetryEvent<'a> {
+    pub method: &'a Method,
+    pub url: &'a Url,
+    /// The attempt that just failed, starting at 1.
+    pub attempt: u32,
+    /// How long the client waits before the next attempt.
+    pub delay: Duration,
+    pub error: &'a ClientError,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(32),
+            jitter: true,
+            retry_mutations: false,
+            on_retry: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("base_delay", &self.base_delay)
+            .field("max_delay", &self.max_delay)
+            .field("jitter", &self.jitter)
+            .field("retry_mutations", &self.retry_mutations)
+            .field("on_retry", &self.on_retry.is_some())
+            .finish()
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self::default().max_attempts(1)
+    }
+
+    /// Total number of attempts, including the first one.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry, doubled on every following one.
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Upper bound of the delay before a retry. A `Retry-After` sent by the server is honoured up to
+    /// this bound, a longer one fails the request instead so that the caller can reschedule it.
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Randomize the backoff so that concurrent clients do not retry in lockstep.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Also retry POST and PATCH requests.
+    pub fn retry_mutations(mut self, retry_mutations: bool) -> Self {
+        self.retry_mutations = retry_mutations;
+        self
+    }
+
+    /// Called right before the client sleeps for a retry, e.g. to log it.
+    pub fn on_retry(mut self, hook: impl Fn(&RetryEvent) + Send + Sync + 'static) -> Self {
+        self.on_retry = Some(Arc::new(hook));
+        self
+    }
+
+    pub(crate) fn should_retry(
+        &self,
+        method: &Method,
+        error: &ClientError,
+        attempt: u32,
+        retry_after: Option<Duration>,
+    ) -> bool {
+        attempt < self.max_attempts
+            && (method.is_idempotent() || self.retry_mutations)
+            && error.is_transient()
+            && retry_after.is_none_or(|retry_after| retry_after <= self.max_delay)
+    }
+
+    pub(crate) fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after;
+        }
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        if !self.jitter {
+            return backoff;
+        }
+        // Equal jitter: keep half of the backoff and randomize the other half.
+        let half = backoff / 2;
+        half + half.mul_f64(random_fraction())
+    }
+
+    pub(crate) fn notify(&self, event: &RetryEvent) {
+        if let Some(hook) = &self.on_retry {
+            hook(event)
+        }
+    }
+}
+
+impl ClientError {
+    /// Whether the request that caused this error may succeed when sent again.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::RateLimited(_) | Self::ServerError(_) | Self::TokenEndpointUnreachable(_) => true,
+            Self::HttpError(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+            _ => false,
+        }
+    }
+}
+
+/// Parse a `Retry-After` header, given either in seconds or as an HTTP date.
+pub(crate) fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(value, Utc::now())
+}
+
+/// Parse the value of a `Retry-After` header relative to `now`. A date in the past means the
+/// request may be sent again right away.
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = http_date(value)?;
+    Some((date - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Parse an HTTP date: the IMF-fixdate format, or one of the obsolete RFC 850 and asctime
+/// formats that recipients must still accept.
+fn http_date(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc2822(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    ["%A, %d-%b-%y %H:%M:%S GMT", "%a %b %e %H:%M:%S %Y"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|date| date.and_utc())
+}
+
+/// A random number in `[0, 1)`, good enough for jitter without pulling in an RNG.
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("1994-11-06T08:49:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn retry_after_in_seconds() {
+        assert_eq!(
+            parse_retry_after(" 120 ", now()),
+            Some(Duration::from_secs(120))
+        );
+    }
+
+    #[test]
+    fn retry_after_as_http_date() {
+        let expected = Some(Duration::from_secs(37));
+        for value in [
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 1994",
+        ] {
+            assert_eq!(parse_retry_after(value, now()), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn retry_after_in_the_past() {
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:00:00 GMT", now()),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn retry_after_invalid() {
+        assert_eq!(parse_retry_after("soon", now()), None);
+    }
+
+    #[test]
+    fn retry_after_past_max_delay() {
+        let policy = RetryPolicy::default().max_delay(Duration::from_secs(60));
+        let error = ClientError::ServerError(Default::default());
+        let retry =
+            |secs| policy.should_retry(&Method::GET, &error, 1, Some(Duration::from_secs(secs)));
+        assert!(retry(60));
+        assert!(!retry(86400));
+    }
+}
@@ -3,11 +3,48 @@ use url::Url;
 
 use super::ClientResult;
 
-const BASE_URL: &str = "https://www.googleapis.com/calendar/v3";
+const GOOGLE_CALENDAR_URL: &str = "https://www.googleapis.com/calendar/v3";
+const GOOGLE_BATCH_URL: &str = "https://www.googleapis.com/batch/calendar/v3";
+const GOOGLE_USERINFO_URL: &str = "https://www.googleapis.com/oauth2/v2/userinfo";
 
 pub type QueryParams = BTreeMap<String, String>;
 pub type AdditionalProperties = BTreeMap<String, String>;
 
+/// Endpoints are the base URLs the client sends its requests to. The defaults point at Google,
+/// but they can be swapped out to target a local stand-in server (e.g. for integration tests).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoints {
+    /// Base of the Calendar API, every calendar, event and calendar list path is appended to it.
+    pub calendar: String,
+    /// The batch endpoint of the Calendar API, see `Batch`.
+    pub batch: String,
+    /// The userinfo endpoint, used by `UserInfo`.
+    pub userinfo: String,
+}
+
+impl Endpoints {
+    pub fn new(calendar: impl ToString, batch: impl ToString, userinfo: impl ToString) -> Self {
+        Self {
+            calendar: calendar.to_string(),
+            batch: batch.to_string(),
+            userinfo: userinfo.to_string(),
+        }
+    }
+
+    /// Whether every endpoint is served over HTTPS.
+    pub fn is_https(&self) -> bool {
+        [&self.calendar, &self.batch, &self.userinfo]
+            .iter()
+            .all(|url| url.starts_with("https://"))
+    }
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self::new(GOOGLE_CALENDAR_URL, GOOGLE_BATCH_URL, GOOGLE_USERINFO_URL)
+    }
+}
+
 /// Sendable is the trait you must implement to interact with the Client. This object is received
 /// by the client and is used to construct the request URL as well as manage the (de)serialization
 /// of the object.
@@ -19,9 +56,14 @@ where
 
     fn query(&self) -> BTreeMap<String, String>;
 
-    fn url(&self, action: Option<String>) -> ClientResult<Url> {
+    /// The base URL the path is appended to. Defaults to the Calendar API endpoint.
+    fn base_url<'a>(&self, endpoints: &'a Endpoints) -> &'a str {
+        &endpoints.calendar
+    }
+
+    fn url(&self, endpoints: &Endpoints, action: Option<String>) -> ClientResult<Url> {
         Ok(Url::parse_with_params(
-            &format!("{}/{}", BASE_URL, self.path(action)),
+            &format!("{}/{}", self.base_url(endpoints), self.path(action)),
             self.query(),
         )?)
     }
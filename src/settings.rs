@@ -0,0 +1,204 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use chrono::{Duration, Weekday};
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    channel::ChannelRequest, pagination, Channel, ClientResult, GCalClient, Page, QueryParams,
+    Sendable,
+};
+
+/* Google Calendar API: https://developers.google.com/calendar/api/v3/reference/settings */
+
+/// Setting is a single user setting, its value is always a string.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Setting {
+    #[serde(
+        default = "default_setting_kind",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub kind: Option<String>,
+    pub etag: String,
+    pub id: String,
+    pub value: String,
+}
+
+impl Sendable for Setting {
+    fn path(&self, _action: Option<String>) -> String {
+        format!("users/me/settings/{}", self.id)
+    }
+
+    fn query(&self) -> QueryParams {
+        Default::default()
+    }
+}
+
+/// Settings is a page of the user's settings.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    #[serde(
+        default = "default_settings_kind",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub kind: Option<String>,
+    pub etag: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_page_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_sync_token: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<Setting>,
+
+    #[serde(skip)]
+    query_string: QueryParams,
+}
+
+impl Settings {
+    pub fn add_query(&mut self, key: String, value: String) {
+        self.query_string.insert(key, value);
+    }
+}
+
+impl Sendable for Settings {
+    fn path(&self, _action: Option<String>) -> String {
+        String::from("users/me/settings")
+    }
+
+    fn query(&self) -> QueryParams {
+        self.query_string.clone()
+    }
+}
+
+impl Page for Settings {
+    type Item = Setting;
+
+    fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
+
+    fn into_items(self) -> Vec<Setting> {
+        self.items
+    }
+}
+
+/// UserSettings holds every setting of the user, with typed accessors for the well-known ones.
+/// Accessors return `None` when the setting is missing or its value cannot be parsed.
+///
+/// Working hours are not part of the settings resource, the API does not expose them.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct UserSettings(BTreeMap<String, String>);
+
+impl UserSettings {
+    /// The raw value of a setting, by ID.
+    pub fn get(&self, id: &str) -> Option<&str> {
+        self.0.get(id).map(String::as_str)
+    }
+
+    /// Every setting, by ID.
+    pub fn all(&self) -> &BTreeMap<String, String> {
+        &self.0
+    }
+
+    /// IANA time zone of the user, e.g. `Europe/Paris`.
+    pub fn timezone(&self) -> Option<&str> {
+        self.get("timezone")
+    }
+
+    /// First day of the week shown in the calendar.
+    pub fn week_start(&self) -> Option<Weekday> {
+        match self.get("weekStart")? {
+            "0" => Some(Weekday::Sun),
+            "1" => Some(Weekday::Mon),
+            "6" => Some(Weekday::Sat),
+            _ => None,
+        }
+    }
+
+    /// Whether times are shown in the 24 hour format.
+    pub fn format_24_hour_time(&self) -> Option<bool> {
+        self.get("format24HourTime")?.parse().ok()
+    }
+
+    /// Language of the user interface, e.g. `en`.
+    pub fn locale(&self) -> Option<&str> {
+        self.get("locale")
+    }
+
+    /// Whether weekends are hidden from the calendar.
+    pub fn hide_weekends(&self) -> Option<bool> {
+        self.get("hideWeekends")?.parse().ok()
+    }
+
+    /// Length of new events.
+    pub fn default_event_length(&self) -> Option<Duration> {
+        Duration::try_minutes(self.get("defaultEventLength")?.parse().ok()?)
+    }
+}
+
+impl FromIterator<Setting> for UserSettings {
+    fn from_iter<I: IntoIterator<Item = Setting>>(iter: I) -> Self {
+        Self(iter.into_iter().map(|s| (s.id, s.value)).collect())
+    }
+}
+
+fn default_setting_kind() -> Option<String> {
+    Some("calendar#setting".to_string())
+}
+fn default_settings_kind() -> Option<String> {
+    Some("calendar#settings".to_string())
+}
+
+/// SettingsClient reads the settings of the authenticated user. Requires a Google Calendar client.
+#[derive(Debug, Clone)]
+pub struct SettingsClient(Arc<GCalClient>);
+
+impl SettingsClient {
+    /// Construct a SettingsClient. Requires a Google Calendar Client.
+    pub fn new(client: Arc<GCalClient>) -> Self {
+        Self(client)
+    }
+
+    /// List every setting, following all pages.
+    pub async fn list(&self) -> ClientResult<UserSettings> {
+        Ok(pagination::collect_items(self.list_stream(), None)
+            .await?
+            .into_iter()
+            .collect())
+    }
+
+    /// Lazily stream the settings, fetching pages as needed.
+    pub fn list_stream(&self) -> impl Stream<Item = ClientResult<Setting>> {
+        pagination::items(self.list_pages())
+    }
+
+    /// Lazily stream the pages of settings.
+    pub fn list_pages(&self) -> impl Stream<Item = ClientResult<Settings>> {
+        pagination::pages(self.0.clone(), |token| {
+            let mut settings = Settings::default();
+            if let Some(token) = token {
+                settings.add_query("pageToken".to_string(), token);
+            }
+            settings
+        })
+    }
+
+    /// Get a setting by ID, e.g. `timezone`.
+    pub async fn get(&self, id: String) -> ClientResult<Setting> {
+        let setting = Setting {
+            id,
+            ..Default::default()
+        };
+        Ok(self.0.get(None, setting).await?.json().await?)
+    }
+
+    /// Watch the settings for changes. The returned channel carries the resource id and
+    /// expiration set by the server.
+    pub async fn watch(&self, channel: Channel) -> ClientResult<Channel> {
+        let target =
+            ChannelRequest::new("users/me/settings/watch".to_string(), Vec::new(), channel);
+        Ok(self.0.post(None, target).await?.json().await?)
+    }
+}
@@ -0,0 +1,80 @@
+use std::{pin::pin, sync::Arc};
+
+use futures::TryStreamExt;
+
+use super::{pagination, ClientError, ClientResult, GCalClient, Page, Sendable};
+
+/* Google API Source: https://developers.google.com/calendar/api/guides/sync */
+
+/// SyncPage is a page of a list response that ends with a sync token on its last page.
+pub trait SyncPage: Page {
+    fn next_sync_token(&self) -> Option<&str>;
+}
+
+/// SyncResult is the outcome of an incremental sync: every item that changed since the previous
+/// sync token, deleted items included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncResult<T> {
+    pub items: Vec<T>,
+    /// Token to hand to the next sync.
+    pub next_sync_token: String,
+    /// Whether a full sync was done, because there was no sync token yet or the server expired it
+    /// (410 Gone). The items are then the complete state and replace any local copy.
+    pub full_resync: bool,
+}
+
+/// Sync from `sync_token`, falling back to a full sync when there is none or it expired. `target`
+/// builds the request of a page from the sync token and the page token.
+pub(crate) async fn sync<S, P>(
+    client: Arc<GCalClient>,
+    target: impl Fn(Option<String>, Option<String>) -> S,
+    sync_token: Option<String>,
+) -> ClientResult<SyncResult<P::Item>>
+where
+    S: Sendable,
+    P: SyncPage,
+{
+    if let Some(token) = sync_token {
+        match sync_from::<S, P>(client.clone(), &target, Some(token)).await {
+            Err(ClientError::Gone(_)) => {
+                tracing::info!("sync token expired, falling back to a full sync");
+            }
+            result => return result,
+        }
+    }
+    Ok(SyncResult {
+        full_resync: true,
+        ..sync_from::<S, P>(client, &target, None).await?
+    })
+}
+
+async fn sync_from<S, P>(
+    client: Arc<GCalClient>,
+    target: &impl Fn(Option<String>, Option<String>) -> S,
+    sync_token: Option<String>,
+) -> ClientResult<SyncResult<P::Item>>
+where
+    S: Sendable,
+    P: SyncPage,
+{
+    let mut items = Vec::new();
+    let mut next_sync_token = None;
+
+    let mut pages = pin!(pagination::pages::<S, P>(client, |page_token| {
+        target(sync_token.clone(), page_token)
+    }));
+    while let Some(page) = pages.try_next().await? {
+        if let Some(token) = page.next_sync_token() {
+            next_sync_token = Some(token.to_string());
+        }
+        items.extend(page.into_items());
+    }
+
+    Ok(SyncResult {
+        items,
+        next_sync_token: next_sync_token.ok_or_else(|| {
+            ClientError::UnknownError("Last page is missing nextSyncToken".to_string())
+        })?,
+        full_resync: false,
+    })
+}
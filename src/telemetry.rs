@@ -0,0 +1,125 @@
+use serde_json::Value;
+
+/// Keys whose values are replaced before a body is logged: personal information and secrets.
+const REDACTED_KEYS: &[&str] = &[
+    "email",
+    "displayName",
+    "description",
+    "location",
+    "comment",
+    "meetingCode",
+    "passcode",
+    "password",
+    "pin",
+    "token",
+    "access_token",
+    "refresh_token",
+    "id_token",
+];
+
+/// Render a request or response body for the logs with personal information and secrets redacted.
+/// Bodies that are not JSON are only described by their size.
+pub(crate) fn redact(body: &[u8]) -> String {
+    match serde_json::from_slice::<Value>(body) {
+        Ok(mut value) => {
+            redact_value(&mut value);
+            value.to_string()
+        }
+        Err(_) => format!("<{} bytes>", body.len()),
+    }
+}
+
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => map.iter_mut().for_each(|(key, value)| {
+            if REDACTED_KEYS.contains(&key.as_str()) {
+                *value = Value::String("[redacted]".to_string());
+            } else {
+                redact_value(value);
+            }
+        }),
+        Value::Array(values) => values.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+/// The calendar a request targets, taken from its `calendars/{id}` path segment.
+pub(crate) fn calendar_id(url: &url::Url) -> Option<String> {
+    let mut segments = url.path_segments()?;
+    segments.find(|s| *s == "calendars")?;
+    segments.next().map(|id| {
+        percent_encoding::percent_decode_str(id)
+            .decode_utf8_lossy()
+            .into_owned()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn redacted(value: Value) -> Value {
+        serde_json::from_str(&redact(value.to_string().as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn nested_attendees() {
+        let event = json!({
+            "summary": "Standup",
+            "attendees": [
+                {"email": "a@example.com", "displayName": "A", "responseStatus": "accepted"},
+                {"email": "b@example.com", "optional": true},
+            ],
+            "organizer": {"email": "c@example.com", "self": true},
+        });
+        assert_eq!(
+            redacted(event),
+            json!({
+                "summary": "Standup",
+                "attendees": [
+                    {"email": "[redacted]", "displayName": "[redacted]", "responseStatus": "accepted"},
+                    {"email": "[redacted]", "optional": true},
+                ],
+                "organizer": {"email": "[redacted]", "self": true},
+            })
+        );
+    }
+
+    #[test]
+    fn secrets() {
+        let body = json!({
+            "id": "channel",
+            "token": "channel-secret",
+            "access_token": "ya29.secret",
+            "refresh_token": "1//secret",
+            "id_token": "eyJ.secret",
+        });
+        assert_eq!(
+            redacted(body),
+            json!({
+                "id": "channel",
+                "token": "[redacted]",
+                "access_token": "[redacted]",
+                "refresh_token": "[redacted]",
+                "id_token": "[redacted]",
+            })
+        );
+    }
+
+    #[test]
+    fn redacts_whole_values() {
+        let body = json!({"location": {"lat": 1, "lng": 2}, "description": null});
+        assert_eq!(
+            redacted(body),
+            json!({"location": "[redacted]", "description": "[redacted]"})
+        );
+    }
+
+    #[test]
+    fn body_that_is_not_json() {
+        let body = b"refresh_token=1//secret&client_secret=secret";
+        assert_eq!(redact(body), format!("<{} bytes>", body.len()));
+    }
+}
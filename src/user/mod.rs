@@ -1,7 +1,5 @@
+use super::{Endpoints, QueryParams, Sendable};
 use serde::{Deserialize, Serialize};
-use url::Url;
-
-use super::{ClientResult, QueryParams, Sendable};
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 #[serde(default)]
@@ -30,14 +28,7 @@ impl Sendable for UserInfo {
         self.query_string.clone()
     }
 
-    fn url(&self, action: Option<String>) -> ClientResult<Url> {
-        Ok(Url::parse_with_params(
-            &format!(
-                "{}/{}",
-                "https://www.googleapis.com/oauth2/v2/userinfo",
-                self.path(action)
-            ),
-            self.query(),
-        )?)
+    fn base_url<'a>(&self, endpoints: &'a Endpoints) -> &'a str {
+        &endpoints.userinfo
     }
 }
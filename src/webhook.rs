@@ -0,0 +1,395 @@
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{Arc, RwLock},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::Stream;
+use http_body_util::{BodyExt, Empty, LengthLimitError, Limited};
+use hyper::{
+    body::{Bytes, Incoming},
+    server::conn::http1,
+    service::service_fn,
+    HeaderMap, Method, Request, Response, StatusCode,
+};
+use hyper_util::rt::{TokioIo, TokioTimer};
+use subtle::ConstantTimeEq;
+use tokio::{
+    net::{TcpListener, ToSocketAddrs},
+    sync::mpsc,
+    task::{JoinHandle, JoinSet},
+};
+use tracing::{debug, warn};
+
+use super::{Channel, ClientResult};
+
+/* Google API Source: https://developers.google.com/calendar/api/guides/push#receiving-notifications */
+
+/// Notifications waiting to be consumed, the receiver answers 503 past this so that Google retries
+/// them later.
+const BUFFER: usize = 1024;
+const HEADER_READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// Notifications carry no body, anything bigger than this is refused.
+const MAX_BODY: usize = 16 * 1024;
+
+/// ResourceState is what a notification says about the watched resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceState {
+    /// The channel was just created, nothing changed yet.
+    Sync,
+    /// The resource was created or changed.
+    Exists,
+    /// The resource was deleted.
+    NotExists,
+}
+impl ResourceState {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Self::Sync => "sync",
+            Self::Exists => "exists",
+            Self::NotExists => "not_exists",
+        }
+    }
+
+    fn parse(state: &str) -> Option<Self> {
+        match state {
+            "sync" => Some(Self::Sync),
+            "exists" => Some(Self::Exists),
+            "not_exists" => Some(Self::NotExists),
+            _ => None,
+        }
+    }
+}
+impl std::fmt::Display for ResourceState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
+/// Notification is a single push notification received on a registered channel. It only says that
+/// the resource changed, fetch the changes with an incremental sync.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub channel_id: String,
+    pub resource_id: String,
+    pub resource_uri: String,
+    pub state: ResourceState,
+    /// Increases with every notification of the channel, `1` for the `sync` one.
+    pub message_number: u64,
+    /// Expiration of the channel, as an HTTP date.
+    pub channel_expiration: Option<String>,
+}
+
+/// Token expected from each registered channel, by channel id.
+type Channels = Arc<RwLock<HashMap<String, Option<String>>>>;
+
+/// WebhookReceiver is an HTTP server receiving the push notifications of registered channels. It
+/// is a stream of the valid notifications, notifications for unknown channels or with the wrong
+/// token are refused. Google requires HTTPS, so it is meant to sit behind a TLS terminating proxy.
+///
+/// The server and its open connections stop when the receiver is dropped.
+#[derive(Debug)]
+pub struct WebhookReceiver {
+    local_addr: SocketAddr,
+    channels: Channels,
+    notifications: mpsc::Receiver<Notification>,
+    server: JoinHandle<()>,
+}
+
+impl WebhookReceiver {
+    /// Start listening for notifications on the address.
+    pub async fn bind(addr: impl ToSocketAddrs) -> ClientResult<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        let channels = Channels::default();
+        let (sender, notifications) = mpsc::channel(BUFFER);
+        let server = tokio::spawn(serve(listener, channels.clone(), sender));
+
+        Ok(Self {
+            local_addr,
+            channels,
+            notifications,
+            server,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Accept the notifications of the channel, checking them against its token.
+    pub fn register(&self, channel: &Channel) {
+        self.channels
+            .write()
+            .expect("Channels lock poisoned")
+            .insert(channel.id.clone(), channel.token.clone());
+    }
+
+    /// Refuse the notifications of the channel from now on.
+    pub fn unregister(&self, channel_id: &str) {
+        self.channels
+            .write()
+            .expect("Channels lock poisoned")
+            .remove(channel_id);
+    }
+}
+
+impl Stream for WebhookReceiver {
+    type Item = Notification;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Notification>> {
+        self.get_mut().notifications.poll_recv(cx)
+    }
+}
+
+impl Drop for WebhookReceiver {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// Accept connections until aborted. Connections are served by tasks of a `JoinSet`, so that
+/// aborting the server drops the set and aborts them as well.
+async fn serve(listener: TcpListener, channels: Channels, sender: mpsc::Sender<Notification>) {
+    let mut connections = JoinSet::new();
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(error) => {
+                warn!(%error, "Failed to accept a webhook connection");
+                continue;
+            }
+        };
+        // Reap the connections that are done, the set would grow forever otherwise.
+        while connections.try_join_next().is_some() {}
+
+        let (channels, sender) = (channels.clone(), sender.clone());
+        connections.spawn(async move {
+            let service = service_fn(move |req| {
+                let (channels, sender) = (channels.clone(), sender.clone());
+                async move { Ok::<_, Infallible>(handle(req, &channels, &sender).await) }
+            });
+            if let Err(error) = http1::Builder::new()
+                .timer(TokioTimer::new())
+                .header_read_timeout(HEADER_READ_TIMEOUT)
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                debug!(%error, %peer, "Webhook connection failed");
+            }
+        });
+    }
+}
+
+async fn handle(
+    req: Request<Incoming>,
+    channels: &Channels,
+    sender: &mpsc::Sender<Notification>,
+) -> Response<Empty<Bytes>> {
+    let status = match notification(req.method(), req.headers(), channels) {
+        Ok(notification) => {
+            // Notifications have no body worth reading, but it must be drained.
+            if let Err(error) = Limited::new(req.into_body(), MAX_BODY).collect().await {
+                if error.is::<LengthLimitError>() {
+                    StatusCode::PAYLOAD_TOO_LARGE
+                } else {
+                    StatusCode::BAD_REQUEST
+                }
+            } else if sender.try_send(notification).is_err() {
+                StatusCode::SERVICE_UNAVAILABLE
+            } else {
+                StatusCode::OK
+            }
+        }
+        Err(status) => status,
+    };
+    let mut response = Response::new(Empty::new());
+    *response.status_mut() = status;
+    response
+}
+
+/// Validate the request against the registered channels and decode its headers.
+fn notification(
+    method: &Method,
+    headers: &HeaderMap,
+    channels: &Channels,
+) -> Result<Notification, StatusCode> {
+    if method != Method::POST {
+        return Err(StatusCode::METHOD_NOT_ALLOWED);
+    }
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    let channel_id = header("X-Goog-Channel-ID").ok_or(StatusCode::BAD_REQUEST)?;
+    match channels
+        .read()
+        .expect("Channels lock poisoned")
+        .get(channel_id)
+    {
+        None => return Err(StatusCode::NOT_FOUND),
+        Some(token) if !token_matches(token.as_deref(), header("X-Goog-Channel-Token")) => {
+            warn!(
+                channel_id,
+                "Webhook notification with a wrong channel token"
+            );
+            return Err(StatusCode::FORBIDDEN);
+        }
+        Some(_) => {}
+    }
+
+    Ok(Notification {
+        channel_id: channel_id.to_string(),
+        resource_id: header("X-Goog-Resource-ID")
+            .ok_or(StatusCode::BAD_REQUEST)?
+            .to_string(),
+        resource_uri: header("X-Goog-Resource-URI")
+            .unwrap_or_default()
+            .to_string(),
+        state: header("X-Goog-Resource-State")
+            .and_then(ResourceState::parse)
+            .ok_or(StatusCode::BAD_REQUEST)?,
+        message_number: header("X-Goog-Message-Number")
+            .and_then(|n| n.parse().ok())
+            .unwrap_or_default(),
+        channel_expiration: header("X-Goog-Channel-Expiration").map(str::to_string),
+    })
+}
+
+/// Compare the channel tokens in constant time, so that their timing does not leak the expected
+/// one.
+fn token_matches(expected: Option<&str>, received: Option<&str>) -> bool {
+    match (expected, received) {
+        (None, None) => true,
+        (Some(expected), Some(received)) => expected.as_bytes().ct_eq(received.as_bytes()).into(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::header::HeaderName;
+
+    use super::*;
+
+    fn channels() -> Channels {
+        let channels = Channels::default();
+        channels.write().unwrap().extend([
+            ("with-token".to_string(), Some("secret".to_string())),
+            ("without-token".to_string(), None),
+        ]);
+        channels
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (HeaderName::from_static(name), value.parse().unwrap()))
+            .collect()
+    }
+
+    fn notify(pairs: &[(&'static str, &str)]) -> Result<Notification, StatusCode> {
+        notification(&Method::POST, &headers(pairs), &channels())
+    }
+
+    #[test]
+    fn decodes_notifications() {
+        for (header, state) in [
+            ("sync", ResourceState::Sync),
+            ("exists", ResourceState::Exists),
+            ("not_exists", ResourceState::NotExists),
+        ] {
+            let notification = notify(&[
+                ("x-goog-channel-id", "with-token"),
+                ("x-goog-channel-token", "secret"),
+                ("x-goog-resource-id", "resource"),
+                ("x-goog-resource-uri", "https://example.com/events"),
+                ("x-goog-resource-state", header),
+                ("x-goog-message-number", "2"),
+                ("x-goog-channel-expiration", "Tue, 19 Nov 2013 01:13:52 GMT"),
+            ]);
+            assert_eq!(
+                notification,
+                Ok(Notification {
+                    channel_id: "with-token".to_string(),
+                    resource_id: "resource".to_string(),
+                    resource_uri: "https://example.com/events".to_string(),
+                    state,
+                    message_number: 2,
+                    channel_expiration: Some("Tue, 19 Nov 2013 01:13:52 GMT".to_string()),
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn channel_without_token() {
+        let notification = notify(&[
+            ("x-goog-channel-id", "without-token"),
+            ("x-goog-resource-id", "resource"),
+            ("x-goog-resource-state", "sync"),
+        ]);
+        assert_eq!(notification.map(|n| n.state), Ok(ResourceState::Sync));
+    }
+
+    #[test]
+    fn unknown_channel() {
+        let notification = notify(&[
+            ("x-goog-channel-id", "unknown"),
+            ("x-goog-resource-id", "resource"),
+            ("x-goog-resource-state", "sync"),
+        ]);
+        assert_eq!(notification, Err(StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn wrong_or_missing_token() {
+        for token in [Some("forged"), Some(""), None] {
+            let mut pairs = vec![
+                ("x-goog-channel-id", "with-token"),
+                ("x-goog-resource-id", "resource"),
+                ("x-goog-resource-state", "sync"),
+            ];
+            pairs.extend(token.map(|token| ("x-goog-channel-token", token)));
+            assert_eq!(notify(&pairs), Err(StatusCode::FORBIDDEN), "{:?}", token);
+        }
+        // A token sent to a channel that has none is just as wrong.
+        let notification = notify(&[
+            ("x-goog-channel-id", "without-token"),
+            ("x-goog-channel-token", "secret"),
+            ("x-goog-resource-id", "resource"),
+            ("x-goog-resource-state", "sync"),
+        ]);
+        assert_eq!(notification, Err(StatusCode::FORBIDDEN));
+    }
+
+    #[test]
+    fn bad_resource_state() {
+        for state in [Some("deleted"), Some("SYNC"), None] {
+            let mut pairs = vec![
+                ("x-goog-channel-id", "without-token"),
+                ("x-goog-resource-id", "resource"),
+            ];
+            pairs.extend(state.map(|state| ("x-goog-resource-state", state)));
+            assert_eq!(notify(&pairs), Err(StatusCode::BAD_REQUEST), "{:?}", state);
+        }
+    }
+
+    #[test]
+    fn not_a_post() {
+        let notification = notification(&Method::GET, &HeaderMap::new(), &channels());
+        assert_eq!(notification, Err(StatusCode::METHOD_NOT_ALLOWED));
+    }
+
+    #[test]
+    fn tokens_compare_exactly() {
+        assert!(token_matches(Some("secret"), Some("secret")));
+        assert!(token_matches(None, None));
+        assert!(!token_matches(Some("secret"), Some("secreT")));
+        assert!(!token_matches(Some("secret"), Some("secret2")));
+        assert!(!token_matches(Some("secret"), None));
+        assert!(!token_matches(None, Some("secret")));
+    }
+}